@@ -38,6 +38,9 @@ class GameBuilder(Generic[A, O]):
         self.payoff_dict: DefaultDict[
             tuple[Optional[tuple[int, int]], Optional[tuple[int, int]]], float
         ] = defaultdict(float)
+        self.chance_dict: DefaultDict[
+            tuple[Optional[tuple[int, int]], Optional[tuple[int, int]]], float
+        ] = defaultdict(float)
         self.obs: tuple[dict[O, int], dict[O, int]] = ({}, {})
         self.obs_list: tuple[list[O], list[O]] = ([], [])
         self.action: tuple[list[list[A]], list[list[A]]] = ([], [])
@@ -64,6 +67,7 @@ class GameBuilder(Generic[A, O]):
         player = state.player()
         if player is None:
             self.payoff_dict[parent] += state.payoff() * prob
+            self.chance_dict[parent] += prob
             return
 
         if player is Player.C:
@@ -97,8 +101,8 @@ class GameBuilder(Generic[A, O]):
 def build(state: GameState[A, O]) -> str:
     game = GameBuilder(state)
 
-    def mat_a():
-        tmp = sorted([(key, val) for key, val in game.payoff_dict.items()])
+    def sparse(values: DefaultDict):
+        tmp = sorted([(key, val) for key, val in values.items()])
         row = []
         col = []
         data = []
@@ -129,7 +133,9 @@ def build(state: GameState[A, O]) -> str:
     obj = {
         "x": sp(0),
         "y": sp(1),
-        "A": mat_a(),
+        "A": sparse(game.payoff_dict),
+        # the chance probabilities of reaching the terminal histories, for the beliefs
+        "C": sparse(game.chance_dict),
     }
 
     return json.dumps(obj)
@@ -38,10 +38,18 @@ def build(key: str, path: str):
         # f.write(game_builder.build(game(key)))
 
 
-def draw(path: str):
+def load_error(path: str):
+    # error.json has the pairs [iteration, error], or the errors of every iteration in older runs
     with open(f"{path}/error.json", "r") as f:
         error = json.load(f)
-    plt.plot(error)
+    if error and isinstance(error[0], list):
+        return [k for k, _ in error], [e for _, e in error]
+    return list(range(len(error))), error
+
+
+def draw(path: str):
+    iter, error = load_error(path)
+    plt.plot(iter, error)
     plt.xscale("log")
     plt.yscale("log")
     plt.xlabel("iterations")
@@ -63,8 +71,7 @@ def draw_multi(logdir: str, game: str, *args):
     for i in range(0, len(args) // 2):
         path = args[2 * i]
         label = args[2 * i + 1]
-        with open(f"{path}/error.json", "r") as f:
-            error = json.load(f)
+        iter, error = load_error(path)
 
         if label in ["EGT-centering", "EGT-centering with CFR+"]:
             start = len(error) // 10
@@ -73,9 +80,8 @@ def draw_multi(logdir: str, game: str, *args):
 
         markevery = [0, len(error) - 1 - start]
 
-        iter = list(range(start, len(error)))
         plt.plot(
-            iter,
+            iter[start:],
             error[start:],
             label=label,
             linewidth=1.0,
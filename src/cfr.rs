@@ -1,7 +1,12 @@
 use crate::game::{Game, StrategyPolytope};
-use indicatif::ProgressIterator;
+use crate::logging;
+use crate::metrics;
+use crate::result::{SolveResult, Weighting};
+use crate::stop;
+use log::{debug, info};
 use ndarray::Array1;
 use std::ops::AddAssign;
+use std::time::Instant;
 
 fn prod(sp: &StrategyPolytope, mut x: Array1<f64>) -> Array1<f64> {
     x[0] = 1.0;
@@ -50,11 +55,36 @@ fn normalize(sp: &StrategyPolytope, regret: Array1<f64>) -> Array1<f64> {
     x
 }
 
-pub fn cfr(game: &Game, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>) {
+fn positive_regret(sp: &StrategyPolytope, regret: &Array1<f64>) -> f64 {
+    // Return the sum of the positive counterfactual regrets over the infosets
+    (0..sp.par.len())
+        .map(|i| (sp.idx[i]..sp.idx[i + 1]).fold(0.0, |acc: f64, j| acc.max(regret[j])))
+        .sum()
+}
+
+fn initial_regret(
+    game: &Game,
+    init: Option<(&Array1<f64>, &Array1<f64>)>,
+) -> (Array1<f64>, Array1<f64>) {
+    // Start from the behavioral strategies of `init` with the regrets of a single step,
+    // so that they are both the first iterate and the first summand of the average.
     let n = *game.sp1.idx.last().unwrap();
     let m = *game.sp2.idx.last().unwrap();
-    let mut regret_x = Array1::<f64>::zeros(n);
-    let mut regret_y = Array1::<f64>::zeros(m);
+    match init {
+        None => (Array1::zeros(n), Array1::zeros(m)),
+        Some((x, y)) => {
+            let scale = game.mat_a.max_abs();
+            (
+                normalize(&game.sp1, x.clone()) * scale,
+                normalize(&game.sp2, y.clone()) * scale,
+            )
+        }
+    }
+}
+
+pub fn cfr(game: &Game, step: usize, init: Option<(&Array1<f64>, &Array1<f64>)>) -> SolveResult {
+    let start = Instant::now();
+    let (mut regret_x, mut regret_y) = initial_regret(game, init);
     let mut z_x = normalize(&game.sp1, regret_x.clone());
     let mut z_y = normalize(&game.sp2, regret_y.clone());
     let mut x = prod(&game.sp1, z_x.clone());
@@ -62,10 +92,13 @@ pub fn cfr(game: &Game, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>) {
     let mut sum_x = x.clone();
     let mut sum_y = y.clone();
 
-    let mut error = vec![game.error(&x, &y)];
-    dbg!(error[0]);
+    let mut error = vec![(0, metrics::observe(0, game, &x, &y, &[]))];
+    debug!("initial error = {:e}", error[0].1);
 
-    for k in (1..step).progress() {
+    for k in logging::progress(1..step) {
+        if stop::requested() {
+            break;
+        }
         accumulate(&game.sp1, &z_x, -game.mat_a.dot(&y), &mut regret_x);
         accumulate(&game.sp2, &z_y, game.mat_a_t.dot(&x), &mut regret_y);
         z_x = normalize(&game.sp1, regret_x.clone().mapv(|v| v.max(0.0)));
@@ -74,17 +107,34 @@ pub fn cfr(game: &Game, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>) {
         y = prod(&game.sp2, z_y.clone());
         sum_x.add_assign(&x);
         sum_y.add_assign(&y);
-        error.push(game.error(&sum_x, &sum_y) / (k + 1) as f64);
+        let regret = positive_regret(&game.sp1, &regret_x) + positive_regret(&game.sp2, &regret_y);
+        error.push((
+            k,
+            metrics::observe(
+                k,
+                game,
+                &(&sum_x / (k + 1) as f64),
+                &(&sum_y / (k + 1) as f64),
+                &[("regret", regret / (k + 1) as f64)],
+            ),
+        ));
     }
-    dbg!(&error[step - 1]);
-    (sum_x / step as f64, sum_y / step as f64, error)
+    info!("final error = {:e}", error.last().unwrap().1);
+    let step = error.len(); // less than the given step if stopped
+    SolveResult::new(x, y, error, start).average(
+        Weighting::Uniform,
+        sum_x / step as f64,
+        sum_y / step as f64,
+    )
 }
 
-pub fn cfr_plus(game: &Game, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>) {
-    let n = *game.sp1.idx.last().unwrap();
-    let m = *game.sp2.idx.last().unwrap();
-    let mut regret_x = Array1::<f64>::zeros(n);
-    let mut regret_y = Array1::<f64>::zeros(m);
+pub fn cfr_plus(
+    game: &Game,
+    step: usize,
+    init: Option<(&Array1<f64>, &Array1<f64>)>,
+) -> SolveResult {
+    let start = Instant::now();
+    let (mut regret_x, mut regret_y) = initial_regret(game, init);
     let mut z_x = normalize(&game.sp1, regret_x.clone());
     let mut z_y = normalize(&game.sp2, regret_y.clone());
     let mut x = prod(&game.sp1, z_x.clone());
@@ -92,10 +142,13 @@ pub fn cfr_plus(game: &Game, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>
     let mut sum_x = x.clone();
     let mut sum_y = y.clone();
 
-    let mut error = vec![game.error(&x, &y)];
-    dbg!(error[0]);
+    let mut error = vec![(0, metrics::observe(0, game, &x, &y, &[]))];
+    debug!("initial error = {:e}", error[0].1);
 
-    for k in (1..step).progress() {
+    for k in logging::progress(1..step) {
+        if stop::requested() {
+            break;
+        }
         accumulate(&game.sp1, &z_x, -game.mat_a.dot(&y), &mut regret_x);
         regret_x.mapv_inplace(|v| v.max(0.0));
         z_x = normalize(&game.sp1, regret_x.clone());
@@ -109,10 +162,21 @@ pub fn cfr_plus(game: &Game, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>
         sum_y.add_assign(&((k + 1) as f64 * &y));
 
         let weight = (k + 1) as f64 * (k + 2) as f64 / 2.0;
-        error.push(game.error(&sum_x, &sum_y) / weight);
+        let regret = positive_regret(&game.sp1, &regret_x) + positive_regret(&game.sp2, &regret_y);
+        error.push((
+            k,
+            metrics::observe(
+                k,
+                game,
+                &(&sum_x / weight),
+                &(&sum_y / weight),
+                &[("regret", regret / (k + 1) as f64)],
+            ),
+        ));
     }
 
-    dbg!(&error[step - 1]);
+    info!("final error = {:e}", error.last().unwrap().1);
+    let step = error.len(); // less than the given step if stopped
     let weight = step as f64 * (step + 1) as f64 / 2.0;
-    (sum_x / weight, sum_y / weight, error)
+    SolveResult::new(x, y, error, start).average(Weighting::Linear, sum_x / weight, sum_y / weight)
 }
@@ -0,0 +1,333 @@
+use crate::game::{Game, StrategyPolytope};
+use crate::logging;
+use crate::metrics;
+use crate::result::{SolveResult, State};
+use crate::stop;
+use log::{debug, info};
+use ndarray::{Array1, Array2};
+use std::time::Instant;
+
+const EPS: f64 = 1e-9;
+
+fn pivot(tab: &mut Array2<f64>, l: usize, e: usize) {
+    // Pivot the tableau on the row l and the column e
+    let p = tab[[l, e]];
+    tab.row_mut(l).mapv_inplace(|v| v / p);
+    let row = tab.row(l).to_owned();
+    for j in 0..tab.nrows() {
+        if j != l {
+            let f = tab[[j, e]];
+            if f != 0.0 {
+                tab.row_mut(j).scaled_add(-f, &row);
+            }
+        }
+    }
+}
+
+fn optimize(tab: &mut Array2<f64>, basis: &mut [usize], cols: usize) -> bool {
+    // Run the simplex method on the tableau whose last row is the reduced costs and
+    // whose last column is the right hand side, entering the first `cols` columns only.
+    // Return false if the problem is unbounded.
+    let m = basis.len();
+    let rhs = tab.ncols() - 1;
+    for it in 0.. {
+        // Dantzig's rule, falling back to Bland's rule against cycling
+        let e = if it < 10 * (m + cols) {
+            (0..cols)
+                .map(|k| (k, tab[[m, k]]))
+                .fold((0, 0.0), |a, v| if v.1 < a.1 { v } else { a })
+                .0
+        } else {
+            (0..cols).find(|&k| tab[[m, k]] < -EPS).unwrap_or(0)
+        };
+        if tab[[m, e]] > -EPS {
+            return true;
+        }
+        let Some(l) = (0..m).filter(|&j| tab[[j, e]] > EPS).min_by(|&a, &b| {
+            let ra = tab[[a, rhs]] / tab[[a, e]];
+            let rb = tab[[b, rhs]] / tab[[b, e]];
+            ra.partial_cmp(&rb).unwrap().then(basis[a].cmp(&basis[b]))
+        }) else {
+            return false;
+        };
+        pivot(tab, l, e);
+        basis[l] = e;
+    }
+    unreachable!()
+}
+
+fn minimize(c: &Array1<f64>, a: &Array2<f64>, b: &Array1<f64>) -> Option<Array1<f64>> {
+    // Return argmin c^Tz s.t. Az = b, z >= 0 by the two-phase simplex method,
+    // or None if the problem is infeasible or unbounded
+    let (m, n) = a.dim();
+    // Columns: z, the artificial variables, then the right hand side
+    let mut tab = Array2::<f64>::zeros((m + 1, n + m + 1));
+    for j in 0..m {
+        let sign = if b[j] < 0.0 { -1.0 } else { 1.0 };
+        for k in 0..n {
+            tab[[j, k]] = sign * a[[j, k]];
+        }
+        tab[[j, n + j]] = 1.0;
+        tab[[j, n + m]] = sign * b[j];
+    }
+    // Phase 1: minimize the sum of the artificial variables
+    for j in 0..m {
+        let row = tab.row(j).to_owned();
+        tab.row_mut(m).scaled_add(-1.0, &row);
+    }
+    for j in 0..m {
+        tab[[m, n + j]] = 0.0;
+    }
+    let mut basis: Vec<usize> = (n..n + m).collect();
+    optimize(&mut tab, &mut basis, n + m);
+    if -tab[[m, n + m]] > EPS * (1.0 + b.iter().fold(0.0f64, |s, v| s.max(v.abs()))) {
+        return None;
+    }
+    // Drive the artificial variables out of the basis; those left are on redundant rows
+    for j in 0..m {
+        if basis[j] >= n {
+            if let Some(e) = (0..n).find(|&k| tab[[j, k]].abs() > EPS) {
+                pivot(&mut tab, j, e);
+                basis[j] = e;
+            }
+        }
+    }
+    // Phase 2: minimize c^Tz
+    tab.row_mut(m).fill(0.0);
+    for k in 0..n {
+        tab[[m, k]] = c[k];
+    }
+    for j in 0..m {
+        if basis[j] < n {
+            let f = tab[[m, basis[j]]];
+            let row = tab.row(j).to_owned();
+            tab.row_mut(m).scaled_add(-f, &row);
+        }
+    }
+    if !optimize(&mut tab, &mut basis, n) {
+        return None;
+    }
+    let mut z = Array1::<f64>::zeros(n);
+    for (j, &k) in basis.iter().enumerate() {
+        if k < n {
+            z[k] = tab[[j, n + m]];
+        }
+    }
+    Some(z)
+}
+
+fn solve_restricted(
+    own: &StrategyPolytope,
+    own_seq: &[bool],
+    opp: &StrategyPolytope,
+    opp_seq: &[bool],
+    payoff: &[(usize, usize, f64)],
+) -> Array1<f64> {
+    // Return the minimizer x of max_y x^TMy over the strategies x of `own` and y of `opp`
+    // supported on the sequences `own_seq` and `opp_seq`, where M is the list `payoff` of
+    // (own sequence, opp sequence, entry). By LP duality, it solves the sequence-form LP
+    //     min v_0 s.t. Ex = e, x >= 0, F^Tv >= M^Tx,
+    // where Ex = e and Fy = f (f = e_0) are the constraints of the restricted polytopes.
+    // The rows of F are the empty sequence and the infosets whose parents are in `opp_seq`.
+    let own_var: Vec<usize> = (0..own_seq.len()).filter(|&j| own_seq[j]).collect();
+    let own_inf: Vec<usize> = (0..own.par.len())
+        .filter(|&i| own_seq[own.par[i]])
+        .collect();
+    let opp_var: Vec<usize> = (0..opp_seq.len()).filter(|&j| opp_seq[j]).collect();
+    let opp_inf: Vec<usize> = (0..opp.par.len())
+        .filter(|&i| opp_seq[opp.par[i]])
+        .collect();
+    let mut col = vec![usize::MAX; own_seq.len()];
+    for (k, &j) in own_var.iter().enumerate() {
+        col[j] = k;
+    }
+    let mut row = vec![usize::MAX; opp_seq.len()];
+    for (k, &j) in opp_var.iter().enumerate() {
+        row[j] = k;
+    }
+
+    // Columns: x, v+ and v- (v = v+ - v-, indexed by the empty sequence then `opp_inf`),
+    // and the slacks of F^Tv >= M^Tx.
+    // Rows: Ex = e (the empty sequence then `own_inf`), then F^Tv - M^Tx - s = 0.
+    let (nx, nv, ns) = (own_var.len(), 1 + opp_inf.len(), opp_var.len());
+    let (m, n) = (1 + own_inf.len() + ns, nx + 2 * nv + ns);
+    let mut a = Array2::<f64>::zeros((m, n));
+    let mut b = Array1::<f64>::zeros(m);
+    a[[0, col[0]]] = 1.0;
+    b[0] = 1.0;
+    for (r, &i) in own_inf.iter().enumerate() {
+        a[[1 + r, col[own.par[i]]]] = -1.0;
+        for j in (own.idx[i]..own.idx[i + 1]).filter(|&j| own_seq[j]) {
+            a[[1 + r, col[j]]] = 1.0;
+        }
+    }
+    let off = 1 + own_inf.len();
+    let mut fv = |j: usize, v: usize, coef: f64| {
+        a[[off + row[j], nx + v]] += coef;
+        a[[off + row[j], nx + nv + v]] -= coef;
+    };
+    fv(0, 0, 1.0);
+    for (r, &i) in opp_inf.iter().enumerate() {
+        fv(opp.par[i], 1 + r, -1.0);
+        for j in (opp.idx[i]..opp.idx[i + 1]).filter(|&j| opp_seq[j]) {
+            fv(j, 1 + r, 1.0);
+        }
+    }
+    for &(i, j, v) in payoff {
+        if own_seq[i] && opp_seq[j] {
+            a[[off + row[j], col[i]]] -= v;
+        }
+    }
+    for k in 0..ns {
+        a[[off + k, nx + 2 * nv + k]] = -1.0;
+    }
+    let mut c = Array1::<f64>::zeros(n);
+    c[nx] = 1.0;
+    c[nx + nv] = -1.0;
+
+    let z = minimize(&c, &a, &b).expect("the restricted game has no solution");
+    let mut x = Array1::<f64>::zeros(own_seq.len());
+    for (k, &j) in own_var.iter().enumerate() {
+        x[j] = z[k].max(0.0);
+    }
+    x
+}
+
+// The numbers of sequences of the restricted game of each player
+pub type Support = (usize, usize);
+
+pub fn double_oracle(game: &Game, step: usize) -> SolveResult {
+    // Sequence-form double oracle (McMahan et al. 2003; Bošanský et al. 2014): the restricted
+    // game is the sequence-form game on the sequences played by the best responses so far,
+    // which is solved exactly as an LP and grown until no best response adds a sequence.
+    let start = Instant::now();
+    let n = *game.sp1.idx.last().unwrap();
+    let m = *game.sp2.idx.last().unwrap();
+    let payoff1: Vec<(usize, usize, f64)> = game.mat_a.entries().collect();
+    let payoff2: Vec<(usize, usize, f64)> =
+        game.mat_a_t.entries().map(|(j, i, v)| (j, i, -v)).collect();
+    let mut x = game.sp1.argmin(Array1::zeros(n));
+    let mut y = game.sp2.argmax(Array1::zeros(m));
+    let mut seq1: Vec<bool> = x.iter().map(|&v| v > 0.0).collect();
+    let mut seq2: Vec<bool> = y.iter().map(|&v| v > 0.0).collect();
+    let count = |seq: &[bool]| seq.iter().filter(|&&b| b).count();
+
+    let mut error = vec![(0, metrics::observe(0, game, &x, &y, &[]))];
+    let mut support = vec![(count(&seq1), count(&seq2))];
+    debug!("initial error = {:e}", error[0].1);
+
+    for k in logging::progress(1..step) {
+        if stop::requested() {
+            break;
+        }
+        let br_x = game.sp1.argmin(game.mat_a.dot(&y));
+        let br_y = game.sp2.argmax(game.mat_a_t.dot(&x));
+        let mut grown = false;
+        for (seq, br) in [(&mut seq1, &br_x), (&mut seq2, &br_y)] {
+            for (s, &v) in seq.iter_mut().zip(br) {
+                if v > 0.0 && !*s {
+                    *s = true;
+                    grown = true;
+                }
+            }
+        }
+        if !grown {
+            break;
+        }
+        x = solve_restricted(&game.sp1, &seq1, &game.sp2, &seq2, &payoff1);
+        y = solve_restricted(&game.sp2, &seq2, &game.sp1, &seq1, &payoff2);
+        error.push((k, metrics::observe(k, game, &x, &y, &[])));
+        support.push((count(&seq1), count(&seq2)));
+    }
+    debug!("{:?} sequences of {:?}", support.last().unwrap(), (n, m));
+    info!("final error = {:e}", error.last().unwrap().1);
+    SolveResult::new(x, y, error, start).state(State::DoubleOracle { support })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::SparseMatrix;
+
+    fn matrix_game(mat: &[&[f64]]) -> Game {
+        // The sequence form of the matrix game with one infoset per player
+        let (r, c) = (mat.len(), mat[0].len());
+        let simplex = |k: usize| StrategyPolytope {
+            par: vec![0],
+            idx: vec![1, 1 + k],
+            obs: vec![],
+            action: vec![],
+        };
+        let (mut row, mut col, mut data) = (vec![], vec![], vec![]);
+        for (i, mat_i) in mat.iter().enumerate() {
+            for (j, &v) in mat_i.iter().enumerate() {
+                row.push(1 + i);
+                col.push(1 + j);
+                data.push(v);
+            }
+        }
+        Game {
+            sp1: simplex(r),
+            sp2: simplex(c),
+            mat_a: SparseMatrix::new(1 + r, &row, &col, &data),
+            mat_a_t: SparseMatrix::new(1 + c, &col, &row, &data),
+            chance: None,
+        }
+    }
+
+    fn solve(game: &Game) -> (Array1<f64>, Array1<f64>) {
+        let seq1 = vec![true; *game.sp1.idx.last().unwrap()];
+        let seq2 = vec![true; *game.sp2.idx.last().unwrap()];
+        let payoff1: Vec<_> = game.mat_a.entries().collect();
+        let payoff2: Vec<_> = game.mat_a_t.entries().map(|(j, i, v)| (j, i, -v)).collect();
+        let x = solve_restricted(&game.sp1, &seq1, &game.sp2, &seq2, &payoff1);
+        let y = solve_restricted(&game.sp2, &seq2, &game.sp1, &seq1, &payoff2);
+        (x, y)
+    }
+
+    fn assert_close(a: &Array1<f64>, b: &[f64]) {
+        assert!(
+            a.iter().zip(b).all(|(u, v)| (u - v).abs() < 1e-9),
+            "{} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn matching_pennies() {
+        let game = matrix_game(&[&[1.0, -1.0], &[-1.0, 1.0]]);
+        let (x, y) = solve(&game);
+        assert_close(&x, &[1.0, 0.5, 0.5]);
+        assert_close(&y, &[1.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn rock_paper_scissors() {
+        let game = matrix_game(&[&[0.0, 1.0, -1.0], &[-1.0, 0.0, 1.0], &[1.0, -1.0, 0.0]]);
+        let (x, y) = solve(&game);
+        let third = 1.0 / 3.0;
+        assert_close(&x, &[1.0, third, third, third]);
+        assert_close(&y, &[1.0, third, third, third]);
+        let result = double_oracle(&game, 10);
+        assert!(result.final_error() < 1e-9);
+    }
+
+    #[test]
+    fn dominated_row() {
+        // The second row is dominated, so the value is that of [[3, 0], [1, 2]]: 3/2
+        let game = matrix_game(&[&[3.0, 0.0], &[4.0, 1.0], &[1.0, 2.0]]);
+        let (x, y) = solve(&game);
+        assert_close(&x, &[1.0, 0.25, 0.0, 0.75]);
+        assert_close(&y, &[1.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn kuhn() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let result = double_oracle(&game, 100);
+        assert!(result.final_error() < 1e-9);
+        let value = result.x.dot(&game.mat_a.dot(&result.y));
+        assert!((value.abs() - 1.0 / 18.0).abs() < 1e-9, "value = {}", value);
+    }
+}
@@ -1,41 +1,124 @@
 use crate::game::Game;
+use crate::logging;
+use crate::metrics;
 use crate::prox_func::ProxFunction;
-use indicatif::ProgressIterator;
+use crate::result::{SolveResult, State};
+use crate::stop;
+use log::{debug, info, warn};
 use ndarray::Array1;
+use std::time::Instant;
 
-pub struct EGT<'a, PF: ProxFunction> {
+// The upper bound of tau grown by the aggressive step size, unless it starts above
+const TAU_MAX: f64 = 0.9;
+
+pub struct EGT<'a, PF1: ProxFunction + ?Sized, PF2: ProxFunction + ?Sized> {
     game: &'a Game,
-    pf1: &'a PF,
-    pf2: &'a PF,
+    pf1: &'a PF1,
+    pf2: &'a PF2,
+    tau: f64,
+    growth: f64,
+    shrink: f64,
+    balance: usize,
+    eps: Option<f64>,
+    evaluate: bool,
+    start: Option<Array1<f64>>,
 }
-impl<'a, PF: ProxFunction> EGT<'a, PF> {
-    pub fn new(game: &'a Game, pf1: &'a PF, pf2: &'a PF) -> Self {
-        Self { game, pf1, pf2 }
+impl<'a, PF1: ProxFunction + ?Sized, PF2: ProxFunction + ?Sized> EGT<'a, PF1, PF2> {
+    pub fn new(game: &'a Game, pf1: &'a PF1, pf2: &'a PF2) -> Self {
+        Self {
+            game,
+            pf1,
+            pf2,
+            tau: 0.5,
+            growth: 1.0,
+            shrink: 0.5,
+            balance: 0,
+            eps: None,
+            evaluate: true,
+            start: None,
+        }
+    }
+    pub fn aggressive(mut self, tau: f64, growth: f64, shrink: f64) -> Self {
+        // Start from `tau`, multiply it by `growth` after each successful step
+        // and by `shrink` on each failure.
+        assert!(0.0 < tau && tau < 1.0, "tau must be in (0, 1)");
+        assert!(growth >= 1.0, "the growth of tau must be at least 1");
+        assert!(
+            0.0 < shrink && shrink < 1.0,
+            "the shrink of tau must be in (0, 1)"
+        );
+        self.tau = tau;
+        self.growth = growth;
+        self.shrink = shrink;
+        self
+    }
+    pub fn balance(mut self, interval: usize) -> Self {
+        // Try to rebalance mu1 and mu2 every `interval` steps.
+        self.balance = interval;
+        self
+    }
+    pub fn certify(mut self, eps: Option<f64>, evaluate: bool) -> Self {
+        // Stop once the certified bound of the gap is at most `eps`, and
+        // evaluate `Game::error` at every step only if `evaluate`.
+        self.eps = eps;
+        self.evaluate = evaluate;
+        self
+    }
+    pub fn start(mut self, x0: Array1<f64>) -> Self {
+        // Find the initial iterates from `x0` instead of the center of `pf1`.
+        self.start = Some(x0);
+        self
     }
     fn excessive_gap(&self, x: &Array1<f64>, y: &Array1<f64>, mu1: f64, mu2: f64) -> f64 {
         let phi: f64 = -mu1 * self.pf1.conj(self.game.mat_a.dot(y) / -mu1);
         let f: f64 = mu2 * self.pf2.conj(self.game.mat_a_t.dot(x) / mu2);
         phi - f
     }
-    fn init(&self, mu1: f64, mu2: f64) -> (Array1<f64>, Array1<f64>) {
-        let y = self
-            .pf2
-            .conj_grad(self.game.mat_a_t.dot(&self.pf1.center()) / mu2);
+    fn init(&self, x0: &Array1<f64>, mu1: f64, mu2: f64) -> (Array1<f64>, Array1<f64>) {
+        let y = self.pf2.conj_grad(self.game.mat_a_t.dot(x0) / mu2);
         let x = self
             .pf1
-            .projection(self.pf1.center().clone(), self.game.mat_a.dot(&y) / mu1);
+            .projection(x0.clone(), self.game.mat_a.dot(&y) / mu1);
         (x, y)
     }
-    fn initialize(&self) -> (Array1<f64>, Array1<f64>, f64) {
-        let mut mu: f64 = 1e-6;
-        loop {
-            let (x, y) = self.init(mu, mu);
-            if self.excessive_gap(&x, &y, mu, mu) > 0.0 {
-                return (x, y, mu);
+    fn initialize(&self, x0: &Array1<f64>) -> Result<(Array1<f64>, Array1<f64>, f64), String> {
+        // Find mu with the positive excessive gap by bracketing [lo, hi]
+        // and then approach the smallest such mu by the bisection on log(mu).
+        // Return an error if no mu <= 1e300 works, where the certified bound is meaningless.
+        let positive = |mu: f64| {
+            let (x, y) = self.init(x0, mu, mu);
+            self.excessive_gap(&x, &y, mu, mu) > 0.0
+        };
+        let mut lo: f64 = 1e-6;
+        let mut hi: f64 = 1e-6;
+        if positive(lo) {
+            while lo > 1e-12 && positive(lo / 10.0) {
+                lo /= 10.0;
+            }
+            hi = lo;
+            lo /= 10.0;
+        } else {
+            while !positive(hi) {
+                if stop::interrupted() {
+                    return Err("interrupted before finding the initial mu".to_string());
+                }
+                lo = hi;
+                hi *= 10.0;
+                if hi > 1e300 {
+                    return Err("no mu <= 1e300 gives a positive excessive gap".to_string());
+                }
             }
-            mu *= 1.2;
-            assert!(mu < 1e9);
         }
+        for _ in 0..10 {
+            let mid = (lo * hi).sqrt();
+            if positive(mid) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        let (x, y) = self.init(x0, hi, hi);
+        Ok((x, y, hi))
     }
     fn shrink_mu1(
         &self,
@@ -86,14 +169,17 @@ impl<'a, PF: ProxFunction> EGT<'a, PF> {
         mu1: f64,
         mu2: f64,
         mut tau: f64,
-    ) -> (Array1<f64>, Array1<f64>, f64, f64) {
+    ) -> Option<(Array1<f64>, Array1<f64>, f64, f64)> {
+        // Return None if tau underflows
         loop {
             let (nxt_x, nxt_y, nxt_mu1) = self.shrink_mu1(x, y, mu1, mu2, tau);
             if self.excessive_gap(&nxt_x, &nxt_y, nxt_mu1, mu2) > 0.0 {
-                return (nxt_x, nxt_y, nxt_mu1, tau);
+                return Some((nxt_x, nxt_y, nxt_mu1, tau));
+            }
+            tau *= self.shrink;
+            if tau <= 1e-20 {
+                return None;
             }
-            tau *= 0.5;
-            assert!(tau > 1e-20);
         }
     }
     fn decrease_mu2(
@@ -103,38 +189,198 @@ impl<'a, PF: ProxFunction> EGT<'a, PF> {
         mu1: f64,
         mu2: f64,
         mut tau: f64,
-    ) -> (Array1<f64>, Array1<f64>, f64, f64) {
+    ) -> Option<(Array1<f64>, Array1<f64>, f64, f64)> {
+        // Return None if tau underflows
         loop {
             let (nxt_x, nxt_y, nxt_mu2) = self.shrink_mu2(x, y, mu1, mu2, tau);
             if self.excessive_gap(&nxt_x, &nxt_y, mu1, nxt_mu2) > 0.0 {
-                return (nxt_x, nxt_y, nxt_mu2, tau);
+                return Some((nxt_x, nxt_y, nxt_mu2, tau));
             }
-            tau *= 0.5;
-            assert!(tau > 1e-20);
+            tau *= self.shrink;
+            if tau <= 1e-20 {
+                return None;
+            }
+        }
+    }
+    fn rebalance(&self, x: &Array1<f64>, y: &Array1<f64>, mu1: f64, mu2: f64) -> (f64, f64) {
+        // Moving mu1 and mu2 to their geometric mean decreases mu1+mu2,
+        // so accept it whenever the excessive gap condition still holds.
+        let mu = (mu1 * mu2).sqrt();
+        if self.excessive_gap(x, y, mu, mu) > 0.0 {
+            (mu, mu)
+        } else {
+            (mu1, mu2)
         }
     }
-    pub fn run(&self, step: usize) -> (Array1<f64>, Array1<f64>, Vec<f64>) {
-        let (mut x, mut y, mu) = self.initialize();
-        dbg!(&mu);
+    pub fn run(&self, step: usize) -> Result<SolveResult, String> {
+        // Also return the certified bounds mu1*D1+mu2*D2 of the gap in the state,
+        // which hold while the excessive gap is nonnegative.
+        let start = Instant::now();
+        let x0 = self.start.as_ref().unwrap_or(self.pf1.center());
+        let (mut x, mut y, mu) = self.initialize(x0)?;
+        debug!("initial mu = {:e}", mu);
         let mut mu1: f64 = mu;
         let mut mu2: f64 = mu;
-        let mut tau: f64 = 0.5;
+        let mut tau: f64 = self.tau;
+        let d1 = self.pf1.max();
+        let d2 = self.pf2.max();
+
+        let fields = |mu1: f64, mu2: f64, tau: f64| {
+            [
+                ("mu1", mu1),
+                ("mu2", mu2),
+                ("tau", tau),
+                ("bound", mu1 * d1 + mu2 * d2),
+            ]
+        };
+        let mut error = vec![(
+            0,
+            metrics::observe(0, self.game, &x, &y, &fields(mu1, mu2, tau)),
+        )];
+        let mut bound = vec![mu1 * d1 + mu2 * d2];
+        debug!("initial error = {:e}", error[0].1);
+        debug!("initial bound = {:e}", bound[0]);
 
-        let mut error = vec![self.game.error(&x, &y)];
-        dbg!(&error[0]);
+        let mut restarts = 0;
 
-        for _ in (1..step).progress() {
-            if mu1 > mu2 {
-                (x, y, mu1, tau) = self.decrease_mu1(&x, &y, mu1, mu2, tau);
+        for k in logging::progress(1..step) {
+            if stop::requested() {
+                break;
+            }
+            let nxt = if mu1 > mu2 {
+                self.decrease_mu1(&x, &y, mu1, mu2, tau)
+                    .map(|(x, y, mu1, tau)| (x, y, mu1, mu2, tau))
             } else {
-                (x, y, mu2, tau) = self.decrease_mu2(&x, &y, mu1, mu2, tau);
+                self.decrease_mu2(&x, &y, mu1, mu2, tau)
+                    .map(|(x, y, mu2, tau)| (x, y, mu1, mu2, tau))
+            };
+            match nxt {
+                Some(nxt) => (x, y, mu1, mu2, tau) = nxt,
+                None => {
+                    // Restart from the current iterate with a fresh mu, or stop if none works
+                    let (x_new, y_new, mu) = match self.initialize(&x) {
+                        Ok(init) => init,
+                        Err(e) => {
+                            warn!(
+                                "tau underflowed at step {} and the restart failed: {}",
+                                k, e
+                            );
+                            break;
+                        }
+                    };
+                    warn!(
+                        "tau underflowed at step {} (mu1 = {:e}, mu2 = {:e}); restarted with mu = {:e}",
+                        k, mu1, mu2, mu
+                    );
+                    (x, y) = (x_new, y_new);
+                    (mu1, mu2, tau) = (mu, mu, self.tau);
+                    restarts += 1;
+                }
+            }
+            if self.growth > 1.0 {
+                tau = (tau * self.growth).min(TAU_MAX.max(self.tau));
+            }
+            if self.balance > 0 && k % self.balance == 0 {
+                (mu1, mu2) = self.rebalance(&x, &y, mu1, mu2);
             }
             // assert!(self.excessive_gap(&x, &y, mu1, mu2) >= 0.0);
-            error.push(self.game.error(&x, &y));
+            bound.push(mu1 * d1 + mu2 * d2);
+            if self.evaluate {
+                error.push((
+                    k,
+                    metrics::observe(k, self.game, &x, &y, &fields(mu1, mu2, tau)),
+                ));
+            }
+            if self.eps.is_some_and(|eps| mu1 * d1 + mu2 * d2 <= eps) {
+                break;
+            }
+        }
+        if !self.evaluate {
+            let k = bound.len() - 1;
+            error.push((
+                k,
+                metrics::observe(k, self.game, &x, &y, &fields(mu1, mu2, tau)),
+            ));
+        }
+        debug!("mu1 = {:e}, mu2 = {:e}, {} restarts", mu1, mu2, restarts);
+        info!(
+            "final bound = {:e} after {} steps",
+            bound.last().unwrap(),
+            bound.len()
+        );
+        info!("final error = {:e}", error.last().unwrap().1);
+        Ok(SolveResult::new(x, y, error, start).state(State::Egt {
+            mu1,
+            mu2,
+            tau,
+            restarts,
+            bound,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prox_func::{DilatedEuclidean, Normal};
+
+    fn run<PF1: ProxFunction + ?Sized, PF2: ProxFunction + ?Sized>(
+        egt: EGT<PF1, PF2>,
+        step: usize,
+    ) -> (Vec<(usize, f64)>, Vec<f64>) {
+        // Return the errors and the certified bounds at every step
+        let result = egt.run(step).unwrap();
+        let State::Egt { bound, .. } = result.state else {
+            panic!("no EGT state");
+        };
+        (result.error, bound)
+    }
+
+    #[test]
+    fn aggressive_and_balanced_bounds() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let pf1 = Normal::new(&game.sp1);
+        let pf2 = Normal::new(&game.sp2);
+        let (_, plain) = run(EGT::new(&game, &pf1, &pf2), 300);
+        let egt = EGT::new(&game, &pf1, &pf2)
+            .aggressive(0.5, 1.2, 0.5)
+            .balance(10);
+        let (error, bound) = run(egt, 300);
+        // The bounds certify the errors at every step
+        for &(k, e) in &error {
+            assert!(
+                e <= bound[k] * (1.0 + 1e-9),
+                "step {}: {:e} > {:e}",
+                k,
+                e,
+                bound[k]
+            );
+        }
+        // and shrink faster than with the fixed tau and no balancing
+        assert!(bound.last().unwrap() < plain.last().unwrap());
+    }
+
+    #[test]
+    fn different_prox_functions() {
+        // The dilated entropy for player 1 and the dilated Euclidean distance for player 2,
+        // through the trait objects as the CLI builds them
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let pf1: Box<dyn ProxFunction> = Box::new(Normal::new(&game.sp1));
+        let pf2: Box<dyn ProxFunction> = Box::new(DilatedEuclidean::new(&game.sp2));
+        let (error, bound) = run(EGT::new(&game, &*pf1, &*pf2), 1000);
+        for &(k, e) in &error {
+            assert!(
+                e <= bound[k] * (1.0 + 1e-9),
+                "step {}: {:e} > {:e}",
+                k,
+                e,
+                bound[k]
+            );
         }
-        dbg!(&mu1);
-        dbg!(&mu2);
-        dbg!(&error[step - 1]);
-        (x, y, error)
+        assert!(
+            error.last().unwrap().1 < 1e-3,
+            "error = {:e}",
+            error.last().unwrap().1
+        );
     }
 }
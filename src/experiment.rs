@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// An experiment file in TOML (or JSON with the same structure), e.g.
+//
+//     game = "leduc.json"
+//     method = "cfr+:10%,egt[prox=centering]:90%"
+//
+//     [stop]
+//     step = 10000
+//     eps = 1e-4
+//
+//     [output]
+//     dir = "log"
+//
+//     [options]
+//     tau_growth = 1.1
+//
+// where `options` takes the options of `solve` with `_` in place of `-`.
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Experiment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub stop: Stop,
+    #[serde(default)]
+    pub output: Output,
+    #[serde(default)]
+    pub options: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Stop {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<usize>, // the number of iterations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eps: Option<f64>, // the target of the certified gap of egt
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Output {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>, // the parent directory of the run directories
+}
+
+fn is_toml(filepath: &str) -> bool {
+    !filepath.ends_with(".json")
+}
+
+impl Experiment {
+    pub fn load(filepath: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(filepath)
+            .map_err(|e| format!("cannot read `{}`: {}", filepath, e))?;
+        let experiment: Self = if is_toml(filepath) {
+            toml::from_str(&content).map_err(|e| format!("invalid `{}`: {}", filepath, e))?
+        } else {
+            serde_json::from_str(&content).map_err(|e| format!("invalid `{}`: {}", filepath, e))?
+        };
+        Ok(experiment)
+    }
+    pub fn save(&self, filepath: &str) -> Result<(), String> {
+        let content = if is_toml(filepath) {
+            toml::to_string(self).map_err(|e| e.to_string())?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        };
+        std::fs::write(filepath, content).map_err(|e| format!("cannot write `{}`: {}", filepath, e))
+    }
+}
@@ -0,0 +1,34 @@
+use crate::game::Game;
+use crate::logging;
+use crate::metrics;
+use crate::result::SolveResult;
+use crate::stop;
+use log::{debug, info};
+use ndarray::Array1;
+use std::time::Instant;
+
+pub fn xfp(game: &Game, step: usize) -> SolveResult {
+    let start = Instant::now();
+    let n = *game.sp1.idx.last().unwrap();
+    let m = *game.sp2.idx.last().unwrap();
+    let mut x = game.sp1.argmin(Array1::zeros(n));
+    let mut y = game.sp2.argmax(Array1::zeros(m));
+
+    let mut error = vec![(0, metrics::observe(0, game, &x, &y, &[]))];
+    debug!("initial error = {:e}", error[0].1);
+
+    for k in logging::progress(1..step) {
+        if stop::requested() {
+            break;
+        }
+        // The mixture of sequence-form strategies is realization equivalent to
+        // the behavioral update of XFP.
+        let br_x = game.sp1.argmin(game.mat_a.dot(&y));
+        let br_y = game.sp2.argmax(game.mat_a_t.dot(&x));
+        x += &((br_x - &x) / (k + 1) as f64);
+        y += &((br_y - &y) / (k + 1) as f64);
+        error.push((k, metrics::observe(k, game, &x, &y, &[])));
+    }
+    info!("final error = {:e}", error.last().unwrap().1);
+    SolveResult::new(x, y, error, start)
+}
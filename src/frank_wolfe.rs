@@ -0,0 +1,240 @@
+use crate::game::{Game, StrategyPolytope};
+use crate::logging;
+use crate::metrics;
+use crate::prox_func::ProxFunction;
+use crate::result::SolveResult;
+use crate::stop;
+use log::{debug, info};
+use ndarray::Array1;
+use std::collections::HashMap;
+use std::time::Instant;
+
+pub fn frank_wolfe(game: &Game, step: usize) -> SolveResult {
+    let start = Instant::now();
+    let n = *game.sp1.idx.last().unwrap();
+    let m = *game.sp2.idx.last().unwrap();
+    let mut x = game.sp1.argmin(Array1::zeros(n));
+    let mut y = game.sp2.argmax(Array1::zeros(m));
+
+    let mut error = vec![(0, metrics::observe(0, game, &x, &y, &[]))];
+    debug!("initial error = {:e}", error[0].1);
+
+    for k in logging::progress(1..step) {
+        if stop::requested() {
+            break;
+        }
+        let gamma = 2.0 / (k + 2) as f64;
+        let s_x = game.sp1.argmin(game.mat_a.dot(&y));
+        let s_y = game.sp2.argmax(game.mat_a_t.dot(&x));
+        x = (1.0 - gamma) * x + gamma * s_x;
+        y = (1.0 - gamma) * y + gamma * s_y;
+        error.push((k, metrics::observe(k, game, &x, &y, &[])));
+    }
+    info!("final error = {:e}", error.last().unwrap().1);
+    SolveResult::new(x, y, error, start)
+}
+
+// The iterate as a convex combination of the vertices,
+// each of which is keyed by its support.
+struct ActiveSet {
+    vertices: HashMap<Vec<usize>, (Array1<f64>, f64)>,
+}
+impl ActiveSet {
+    fn new(v: Array1<f64>) -> Self {
+        let mut vertices = HashMap::new();
+        vertices.insert(support(&v), (v, 1.0));
+        Self { vertices }
+    }
+    fn step(
+        &mut self,
+        sp: &StrategyPolytope,
+        x: Array1<f64>,
+        g: Array1<f64>,
+        gamma: f64,
+    ) -> Array1<f64> {
+        // Move x along the better of the Frank-Wolfe and the away direction for min <g, x>.
+        // The away step is taken only if it drops its vertex within the step size `gamma`:
+        // the longer away steps of the open-loop step sizes make both players jump between
+        // the vertices like best responses, which cycles around a saddle point.
+        let s = sp.argmin(g.clone());
+        let gap_fw = g.dot(&(&x - &s));
+        let (key_a, gap_a) = self
+            .vertices
+            .iter()
+            .map(|(key, (v, _))| (key, g.dot(&(v - &x))))
+            .fold((None, f64::NEG_INFINITY), |m, (key, gap)| {
+                if gap > m.1 {
+                    (Some(key.clone()), gap)
+                } else {
+                    m
+                }
+            });
+        let key_a = key_a.unwrap();
+        let (v_a, w_a) = self.vertices[&key_a].clone();
+        let gamma_max = w_a / (1.0 - w_a);
+        if gap_fw >= gap_a || gamma_max > gamma {
+            for (_, w) in self.vertices.values_mut() {
+                *w *= 1.0 - gamma;
+            }
+            let x_nxt = (1.0 - gamma) * x + gamma * &s;
+            self.vertices.entry(support(&s)).or_insert((s, 0.0)).1 += gamma;
+            self.vertices.retain(|_, (_, w)| *w > 0.0);
+            x_nxt
+        } else {
+            for (_, w) in self.vertices.values_mut() {
+                *w *= 1.0 + gamma_max;
+            }
+            self.vertices.remove(&key_a);
+            (1.0 + gamma_max) * x - gamma_max * &v_a
+        }
+    }
+}
+
+fn support(v: &Array1<f64>) -> Vec<usize> {
+    v.iter()
+        .enumerate()
+        .filter(|(_, &x)| x > 0.0)
+        .map(|(j, _)| j)
+        .collect()
+}
+
+pub fn away_frank_wolfe(game: &Game, step: usize) -> SolveResult {
+    let start = Instant::now();
+    let n = *game.sp1.idx.last().unwrap();
+    let m = *game.sp2.idx.last().unwrap();
+    let mut x = game.sp1.argmin(Array1::zeros(n));
+    let mut y = game.sp2.argmax(Array1::zeros(m));
+    let mut active_x = ActiveSet::new(x.clone());
+    let mut active_y = ActiveSet::new(y.clone());
+
+    let mut error = vec![(0, metrics::observe(0, game, &x, &y, &[]))];
+    debug!("initial error = {:e}", error[0].1);
+
+    for k in logging::progress(1..step) {
+        if stop::requested() {
+            break;
+        }
+        let gamma = 2.0 / (k + 2) as f64;
+        let g_x = game.mat_a.dot(&y);
+        let g_y = -game.mat_a_t.dot(&x);
+        x = active_x.step(&game.sp1, x, g_x, gamma);
+        y = active_y.step(&game.sp2, y, g_y, gamma);
+        error.push((k, metrics::observe(k, game, &x, &y, &[])));
+    }
+    debug!(
+        "{} and {} active vertices",
+        active_x.vertices.len(),
+        active_y.vertices.len()
+    );
+    info!("final error = {:e}", error.last().unwrap().1);
+    SolveResult::new(x, y, error, start)
+}
+
+// Frank-Wolfe on the smoothed objectives
+// max_y x^TAy - μd2(y) and min_x x^TAy + μd1(x) with μ = μ0/sqrt(k+1).
+pub struct SmoothedFW<'a, PF: ProxFunction> {
+    game: &'a Game,
+    pf1: &'a PF,
+    pf2: &'a PF,
+    mu: f64,
+}
+impl<'a, PF: ProxFunction> SmoothedFW<'a, PF> {
+    pub fn new(game: &'a Game, pf1: &'a PF, pf2: &'a PF, mu: f64) -> Self {
+        Self { game, pf1, pf2, mu }
+    }
+    pub fn run(&self, step: usize) -> SolveResult {
+        let start = Instant::now();
+        let mut x = self.pf1.center().clone();
+        let mut y = self.pf2.center().clone();
+
+        let mut error = vec![(0, metrics::observe(0, self.game, &x, &y, &[]))];
+        debug!("initial error = {:e}", error[0].1);
+
+        for k in logging::progress(1..step) {
+            if stop::requested() {
+                break;
+            }
+            let gamma = 2.0 / (k + 2) as f64;
+            let mu = self.mu / ((k + 1) as f64).sqrt();
+            let y_mu = self.pf2.conj_grad(self.game.mat_a_t.dot(&x) / mu);
+            let x_mu = self.pf1.conj_grad(self.game.mat_a.dot(&y) / -mu);
+            let s_x = self.game.sp1.argmin(self.game.mat_a.dot(&y_mu));
+            let s_y = self.game.sp2.argmax(self.game.mat_a_t.dot(&x_mu));
+            x = (1.0 - gamma) * x + gamma * s_x;
+            y = (1.0 - gamma) * y + gamma * s_y;
+            error.push((k, metrics::observe(k, self.game, &x, &y, &[])));
+        }
+        info!("final error = {:e}", error.last().unwrap().1);
+        SolveResult::new(x, y, error, start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::SparseMatrix;
+    use crate::prox_func::Normal;
+
+    fn matrix_game(mat: &[&[f64]]) -> Game {
+        // The sequence form of the matrix game with one infoset per player
+        let (r, c) = (mat.len(), mat[0].len());
+        let simplex = |k: usize| StrategyPolytope {
+            par: vec![0],
+            idx: vec![1, 1 + k],
+            obs: vec![],
+            action: vec![],
+        };
+        let (mut row, mut col, mut data) = (vec![], vec![], vec![]);
+        for (i, mat_i) in mat.iter().enumerate() {
+            for (j, &v) in mat_i.iter().enumerate() {
+                row.push(1 + i);
+                col.push(1 + j);
+                data.push(v);
+            }
+        }
+        Game {
+            sp1: simplex(r),
+            sp2: simplex(c),
+            mat_a: SparseMatrix::new(1 + r, &row, &col, &data),
+            mat_a_t: SparseMatrix::new(1 + c, &col, &row, &data),
+            chance: None,
+        }
+    }
+
+    #[test]
+    fn converge_on_matrix_games() {
+        for mat in [
+            &[&[1.0, -1.0][..], &[-1.0, 1.0]][..],
+            &[&[0.0, 2.0, -1.0], &[-1.0, 0.0, 1.0], &[1.0, -1.0, 0.0]],
+            &[&[3.0, 0.0], &[4.0, 1.0], &[1.0, 2.0]],
+        ] {
+            let game = matrix_game(mat);
+            // The error decreases at least as O(1/sqrt(k))
+            for (name, result) in [
+                ("fw", frank_wolfe(&game, 10000)),
+                ("afw", away_frank_wolfe(&game, 10000)),
+            ] {
+                let error = |k: usize| result.error[k].1;
+                assert!(
+                    error(9999) < error(99) / 5.0,
+                    "{}: {:e} vs {:e}",
+                    name,
+                    error(9999),
+                    error(99)
+                );
+                for x in [&result.x, &result.y] {
+                    assert!((x.sum() - 2.0).abs() < 1e-9 && x.iter().all(|&v| v >= 0.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smoothed_converges_on_kuhn() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let pf1 = Normal::new(&game.sp1);
+        let pf2 = Normal::new(&game.sp2);
+        let result = SmoothedFW::new(&game, &pf1, &pf2, 1.0).run(10000);
+        assert!(result.final_error() < result.error[99].1 / 5.0);
+    }
+}
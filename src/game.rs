@@ -1,12 +1,15 @@
 use ndarray::{s, Array1};
 use serde_json::Value;
+use std::collections::HashMap;
 
 pub struct StrategyPolytope {
     pub par: Vec<usize>,
     pub idx: Vec<usize>,
+    pub obs: Vec<String>, // the label of each infoset (empty if the game has none)
+    pub action: Vec<Vec<String>>, // the labels of the actions at each infoset
 }
 impl StrategyPolytope {
-    fn minimize(&self, mut c: Array1<f64>) -> f64 {
+    pub fn minimize(&self, mut c: Array1<f64>) -> f64 {
         for (i, &p) in self.par.iter().enumerate().rev() {
             let min = c
                 .slice(s![self.idx[i]..self.idx[i + 1]])
@@ -15,7 +18,7 @@ impl StrategyPolytope {
         }
         c[0]
     }
-    fn maximize(&self, mut c: Array1<f64>) -> f64 {
+    pub fn maximize(&self, mut c: Array1<f64>) -> f64 {
         for (i, &p) in self.par.iter().enumerate().rev() {
             let max = c
                 .slice(s![self.idx[i]..self.idx[i + 1]])
@@ -24,13 +27,122 @@ impl StrategyPolytope {
         }
         c[0]
     }
+    fn best(&self, mut c: Array1<f64>, sign: f64) -> Array1<f64> {
+        // Return the vertex minimizing <sign*c, x>
+        let mut best = vec![0; self.par.len()];
+        for (i, &p) in self.par.iter().enumerate().rev() {
+            let (j, min) = (self.idx[i]..self.idx[i + 1])
+                .map(|j| (j, sign * c[j]))
+                .fold((0, f64::INFINITY), |m, v| if v.1 < m.1 { v } else { m });
+            best[i] = j;
+            c[p] += sign * min;
+        }
+        let mut x: Array1<f64> = Array1::zeros(c.len());
+        x[0] = 1.0;
+        for (i, &p) in self.par.iter().enumerate() {
+            x[best[i]] = x[p];
+        }
+        x
+    }
+    pub fn argmin(&self, c: Array1<f64>) -> Array1<f64> {
+        self.best(c, 1.0)
+    }
+    pub fn argmax(&self, c: Array1<f64>) -> Array1<f64> {
+        self.best(c, -1.0)
+    }
+    pub fn smooth(&self, x: &Array1<f64>, floor: f64) -> Array1<f64> {
+        // Return the strategy playing the behavioral strategy of `x` clipped at `floor`
+        // and renormalized at each infoset, which is uniform where `x` has no mass.
+        // Panic on a NaN, infinite or negative entry, reporting its infoset.
+        let mut z: Array1<f64> = Array1::zeros(x.len());
+        z[0] = 1.0;
+        for (i, &p) in self.par.iter().enumerate() {
+            let (l, r) = (self.idx[i], self.idx[i + 1]);
+            if let Some(j) = (l..r).find(|&j| !x[j].is_finite() || x[j] < 0.0) {
+                panic!("invalid strategy at infoset {}: x[{}] = {}", i, j, x[j]);
+            }
+            let sum = x.slice(s![l..r]).sum();
+            let b: Vec<f64> = (l..r)
+                .map(|j| {
+                    let b = if sum > 0.0 {
+                        x[j] / sum
+                    } else {
+                        1.0 / (r - l) as f64
+                    };
+                    b.max(floor)
+                })
+                .collect();
+            let total: f64 = b.iter().sum();
+            for (j, b) in (l..r).zip(b) {
+                z[j] = z[p] * b / total;
+            }
+        }
+        z
+    }
+    pub fn uniform(&self) -> Array1<f64> {
+        self.smooth(&Array1::zeros(*self.idx.last().unwrap()), 0.0)
+    }
+    pub fn to_labels(&self, x: &Array1<f64>) -> HashMap<String, HashMap<String, f64>> {
+        // Return the behavioral strategy {obs: {action: prob}} of `x`,
+        // which is uniform at the infosets where `x` has no mass
+        let x = self.smooth(x, 0.0);
+        (0..self.par.len())
+            .map(|i| {
+                let b = (self.idx[i]..self.idx[i + 1])
+                    .zip(&self.action[i])
+                    .map(|(j, action)| (action.clone(), x[j] / x[self.par[i]]))
+                    .collect();
+                (self.obs[i].clone(), b)
+            })
+            .collect()
+    }
+    pub fn from_labels(
+        &self,
+        strategy: &HashMap<String, HashMap<String, f64>>,
+    ) -> Result<Array1<f64>, String> {
+        // Return the strategy playing the behavioral strategy {obs: {action: prob}},
+        // which is uniform at the infosets missing from `strategy`.
+        if let Some(obs) = strategy.keys().find(|obs| !self.obs.contains(obs)) {
+            return Err(format!("unknown infoset `{}`", obs));
+        }
+        let mut x: Array1<f64> = Array1::zeros(*self.idx.last().unwrap());
+        x[0] = 1.0;
+        for (i, &p) in self.par.iter().enumerate() {
+            let (l, r) = (self.idx[i], self.idx[i + 1]);
+            let Some(b) = strategy.get(&self.obs[i]) else {
+                for j in l..r {
+                    x[j] = x[p] / (r - l) as f64;
+                }
+                continue;
+            };
+            if let Some(action) = b.keys().find(|action| !self.action[i].contains(action)) {
+                return Err(format!(
+                    "unknown action `{}` at infoset `{}` (actions: {})",
+                    action,
+                    self.obs[i],
+                    self.action[i].join(", ")
+                ));
+            }
+            let total: f64 = b.values().sum();
+            if !(total > 0.0 && total.is_finite()) || b.values().any(|&p| p < 0.0) {
+                return Err(format!(
+                    "no probability distribution at infoset `{}`",
+                    self.obs[i]
+                ));
+            }
+            for (j, action) in (l..r).zip(&self.action[i]) {
+                x[j] = x[p] * b.get(action).unwrap_or(&0.0) / total;
+            }
+        }
+        Ok(x)
+    }
 }
 
 pub struct SparseMatrix {
     cols: Vec<Vec<(usize, f64)>>,
 }
 impl SparseMatrix {
-    fn new(n_row: usize, row: &Vec<usize>, col: &Vec<usize>, data: &Vec<f64>) -> Self {
+    pub(crate) fn new(n_row: usize, row: &[usize], col: &[usize], data: &[f64]) -> Self {
         let mut cols = vec![vec![]; n_row];
         for ((&r, &c), &d) in row.iter().zip(col).zip(data) {
             cols[r].push((c, d));
@@ -38,12 +150,24 @@ impl SparseMatrix {
         Self { cols }
     }
     pub fn dot(&self, rhs: &Array1<f64>) -> Array1<f64> {
-        return self
-            .cols
+        self.cols
             .iter()
             .map(|col| col.iter().map(|(c, d)| rhs[*c] * d).sum())
             .collect::<Vec<_>>()
-            .into();
+            .into()
+    }
+    pub fn entries(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        // Iterate over the nonzero entries (row, column, value)
+        (self.cols.iter().enumerate()).flat_map(|(r, col)| col.iter().map(move |&(c, d)| (r, c, d)))
+    }
+    pub fn nnz(&self) -> usize {
+        self.cols.iter().map(|col| col.len()).sum()
+    }
+    pub fn max_abs(&self) -> f64 {
+        self.cols
+            .iter()
+            .flatten()
+            .fold(0.0, |m, (_, d)| d.abs().max(m))
     }
 }
 
@@ -52,71 +176,168 @@ pub struct Game {
     pub sp2: StrategyPolytope,
     pub mat_a: SparseMatrix,
     pub mat_a_t: SparseMatrix,
+    // The chance probabilities C and C^T of reaching the terminal histories, laid out like A,
+    // which give the beliefs at the infosets; absent from the game files built without them
+    pub chance: Option<(SparseMatrix, SparseMatrix)>,
 }
 impl Game {
-    pub fn load(filepath: &str) -> Self {
-        let json = {
-            let file_content = std::fs::read_to_string(filepath).unwrap();
-            serde_json::from_str::<Value>(&file_content).unwrap()
+    pub fn load(filepath: &str) -> Result<Self, String> {
+        let file_content = std::fs::read_to_string(filepath)
+            .map_err(|e| format!("cannot read `{}`: {}", filepath, e))?;
+        let json = serde_json::from_str::<Value>(&file_content)
+            .map_err(|e| format!("invalid `{}`: {}", filepath, e))?;
+        let invalid = |key: &str| {
+            format!(
+                "invalid `{}`: `{}` is not an array of numbers",
+                filepath, key
+            )
         };
-        let sp1 = StrategyPolytope {
-            par: json["x"]["par"]
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|x| x.as_u64().unwrap() as usize)
-                .collect(),
-            idx: json["x"]["idx"]
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|x| x.as_u64().unwrap() as usize)
-                .collect(),
+        let indices = |v: &Value, key: &str| -> Result<Vec<usize>, String> {
+            (v.as_array().ok_or_else(|| invalid(key))?.iter())
+                .map(|x| x.as_u64().map(|x| x as usize).ok_or_else(|| invalid(key)))
+                .collect()
         };
-        let sp2 = StrategyPolytope {
-            par: json["y"]["par"]
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|x| x.as_u64().unwrap() as usize)
-                .collect(),
-            idx: json["y"]["idx"]
-                .as_array()
-                .unwrap()
+        let polytope = |player: &str| -> Result<StrategyPolytope, String> {
+            let sp = StrategyPolytope {
+                par: indices(&json[player]["par"], &format!("{}.par", player))?,
+                idx: indices(&json[player]["idx"], &format!("{}.idx", player))?,
+                obs: serde_json::from_value(json[player]["obs"].clone()).unwrap_or_default(),
+                action: serde_json::from_value(json[player]["action"].clone()).unwrap_or_default(),
+            };
+            if sp.idx.len() != sp.par.len() + 1 {
+                return Err(format!(
+                    "invalid `{}`: `{}.idx` must have one more entry than `{}.par`",
+                    filepath, player, player
+                ));
+            }
+            Ok(sp)
+        };
+        let sp1 = polytope("x")?;
+        let sp2 = polytope("y")?;
+        let (n, m) = (*sp1.idx.last().unwrap(), *sp2.idx.last().unwrap());
+        // Return the matrix and its transpose
+        let matrix = |key: &str| -> Result<(SparseMatrix, SparseMatrix), String> {
+            let row = indices(&json[key]["row"], &format!("{}.row", key))?;
+            let col = indices(&json[key]["col"], &format!("{}.col", key))?;
+            let data: Vec<f64> = (json[key]["data"].as_array())
+                .ok_or_else(|| invalid(&format!("{}.data", key)))?
                 .iter()
-                .map(|x| x.as_u64().unwrap() as usize)
-                .collect(),
+                .map(|x| x.as_f64().ok_or_else(|| invalid(&format!("{}.data", key))))
+                .collect::<Result<_, _>>()?;
+            if row.len() != data.len() || col.len() != data.len() {
+                return Err(format!(
+                    "invalid `{}`: `{}.row`, `{}.col` and `{}.data` differ in length",
+                    filepath, key, key, key
+                ));
+            }
+            if row.iter().any(|&r| r >= n) || col.iter().any(|&c| c >= m) {
+                return Err(format!(
+                    "invalid `{}`: `{}` has an entry out of the {}x{} sequences",
+                    filepath, key, n, m
+                ));
+            }
+            Ok((
+                SparseMatrix::new(n, &row, &col, &data),
+                SparseMatrix::new(m, &col, &row, &data),
+            ))
         };
-        let row: Vec<usize> = json["A"]["row"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|x| x.as_u64().unwrap() as usize)
-            .collect();
-        let col: Vec<usize> = json["A"]["col"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|x| x.as_u64().unwrap() as usize)
-            .collect();
-        let data: Vec<f64> = json["A"]["data"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|x| x.as_f64().unwrap())
-            .collect();
-        let mat_a = SparseMatrix::new(*sp1.idx.last().unwrap(), &row, &col, &data);
-        let mat_a_t = SparseMatrix::new(*sp2.idx.last().unwrap(), &col, &row, &data);
-        Self {
+        let (mat_a, mat_a_t) = matrix("A")?;
+        let chance = match json.get("C") {
+            Some(_) => Some(matrix("C")?),
+            None => None,
+        };
+        Ok(Self {
             sp1,
             sp2,
             mat_a,
             mat_a_t,
+            chance,
+        })
+    }
+    pub fn spectral_norm(&self) -> f64 {
+        // Return ||A||_2 by the power iteration on A^TA
+        let mut y = Array1::<f64>::ones(*self.sp2.idx.last().unwrap());
+        let mut norm = 0.0;
+        for _ in 0..100 {
+            let z = self.mat_a_t.dot(&self.mat_a.dot(&y));
+            norm = z.dot(&z).sqrt() / y.dot(&y).sqrt();
+            y = z / norm;
         }
+        norm.sqrt()
     }
     pub fn error(&self, x: &Array1<f64>, y: &Array1<f64>) -> f64 {
-        let min: f64 = self.sp1.minimize(self.mat_a.dot(y));
+        self.evaluate(x, y).0
+    }
+    pub fn evaluate(&self, x: &Array1<f64>, y: &Array1<f64>) -> (f64, f64) {
+        // Return the error and the value x^TAy
+        let ay = self.mat_a.dot(y);
+        let value = x.dot(&ay);
+        let min: f64 = self.sp1.minimize(ay);
         let max: f64 = self.sp2.maximize(self.mat_a_t.dot(x));
-        max - min
+        (max - min, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_round_trip() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        for sp in [&game.sp1, &game.sp2] {
+            // A strategy with unreached infosets, which `to_labels` makes uniform
+            let n = *sp.idx.last().unwrap();
+            let mut x = sp.argmin(Array1::from_iter((0..n).map(|j| (j * 7 % 5) as f64)));
+            x += &(0.5 * &sp.argmax(Array1::from_iter((0..n).map(|j| (j % 3) as f64))));
+            let y = sp.from_labels(&sp.to_labels(&x)).unwrap();
+            let z = sp.smooth(&x, 0.0);
+            assert!((&y - &z).iter().all(|d| d.abs() < 1e-12), "{} vs {}", y, z);
+        }
+    }
+
+    #[test]
+    fn unknown_labels() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let strategy = |obs: &str, action: &str| {
+            HashMap::from([(obs.to_string(), HashMap::from([(action.to_string(), 1.0)]))])
+        };
+        assert!(game.sp1.from_labels(&strategy("J", "Check")).is_ok());
+        assert!(game.sp1.from_labels(&strategy("J,Raise", "Check")).is_err());
+        assert!(game.sp1.from_labels(&strategy("J", "Raise")).is_err());
+    }
+
+    #[test]
+    fn smooth_floors_and_fills_unreached_infosets() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let sp = &game.sp1;
+        let n = *sp.idx.last().unwrap();
+        // A pure strategy, which leaves half of the infosets unreached
+        let x = sp.argmin(Array1::from_iter((0..n).map(|j| (j % 2) as f64)));
+        assert_eq!(sp.smooth(&x, 0.0), x);
+        let floor = 0.1;
+        let z = sp.smooth(&x, floor);
+        for (i, &p) in sp.par.iter().enumerate() {
+            let (l, r) = (sp.idx[i], sp.idx[i + 1]);
+            let sum: f64 = (l..r).map(|j| z[j]).sum();
+            assert!((sum - z[p]).abs() < 1e-12);
+            for j in l..r {
+                // Every action is played with at least floor/(1+|actions|*floor)
+                let b = z[j] / z[p];
+                assert!(b >= floor / (1.0 + (r - l) as f64 * floor) - 1e-12, "{}", b);
+                if x[p] == 0.0 {
+                    assert!((b - 1.0 / (r - l) as f64).abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid strategy at infoset")]
+    fn smooth_rejects_negative_entries() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let mut x = game.sp1.uniform();
+        x[1] = -0.5;
+        game.sp1.smooth(&x, 0.0);
     }
 }
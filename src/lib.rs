@@ -1,4 +1,20 @@
 pub mod cfr;
+pub mod double_oracle;
 pub mod egt;
+pub mod experiment;
+pub mod fictitious_play;
+pub mod frank_wolfe;
 pub mod game;
+pub mod logging;
+pub mod manifest;
+pub mod metrics;
+pub mod mirror_prox;
+pub mod mmd;
+pub mod omd;
+pub mod pdhg;
+pub mod plot;
 pub mod prox_func;
+pub mod qre;
+pub mod result;
+pub mod schedule;
+pub mod stop;
@@ -0,0 +1,94 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressBarIter, ProgressDrawTarget};
+use indicatif::{ProgressIterator, ProgressStyle};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::cell::RefCell;
+use std::sync::OnceLock;
+
+// The logger of the `log` facade and the progress bars of the solvers, which share stderr:
+// log lines are printed above the bars. The bar of a thread shows the latest error
+// recorded by `metrics::record`, with EGT's mu1 and mu2 if the debug level is enabled.
+
+static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+thread_local! {
+    static BAR: RefCell<Option<ProgressBar>> = const { RefCell::new(None) };
+}
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+fn bars() -> &'static MultiProgress {
+    BARS.get_or_init(MultiProgress::new)
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let line = format!("[{} {}] {}", record.level(), record.target(), record.args());
+            bars().suspend(|| eprintln!("{}", line));
+        }
+    }
+    fn flush(&self) {}
+}
+
+pub fn init(level: LevelFilter, quiet: bool) {
+    // Log at `level` and hide the progress bars if `quiet`
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(level);
+    if quiet {
+        bars().set_draw_target(ProgressDrawTarget::hidden());
+    }
+}
+
+pub fn progress<I: ExactSizeIterator>(iter: I) -> ProgressBarIter<I> {
+    // Wrap the loop of a solver with the progress bar of the current thread
+    let style = ProgressStyle::with_template(
+        "{wide_bar} {pos}/{len} [{elapsed_precise}<{eta_precise}] {msg}",
+    )
+    .unwrap();
+    let bar = bars().add(ProgressBar::new(iter.len() as u64).with_style(style));
+    BAR.with(|b| *b.borrow_mut() = Some(bar.clone()));
+    iter.progress_with(bar)
+}
+
+fn message(error: f64, fields: &[(&str, f64)], debug: bool) -> String {
+    // The message of the bar: the error, with mu1 and mu2 if `debug`
+    let mut message = format!("error {:.3e}", error);
+    if debug {
+        for (key, value) in fields
+            .iter()
+            .filter(|(key, _)| ["mu1", "mu2"].contains(key))
+        {
+            message += &format!(" {} {:.3e}", key, value);
+        }
+    }
+    message
+}
+
+pub fn report(error: f64, fields: &[(&str, f64)]) {
+    BAR.with(|b| {
+        if let Some(bar) = b.borrow().as_ref().filter(|bar| !bar.is_finished()) {
+            bar.set_message(message(error, fields, log::log_enabled!(Level::Debug)));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_shows_mu_at_debug() {
+        let fields = [("mu1", 0.5), ("tau", 0.1), ("mu2", 0.25)];
+        assert_eq!(message(0.0123, &fields, false), "error 1.230e-2");
+        assert_eq!(
+            message(0.0123, &fields, true),
+            "error 1.230e-2 mu1 5.000e-1 mu2 2.500e-1"
+        );
+        assert_eq!(message(1.0, &[("regret", 2.0)], true), "error 1.000e0");
+    }
+}
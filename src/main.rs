@@ -1,90 +1,1485 @@
 // extern crate blas_src;
 
 use egt_on_efg::cfr::{cfr, cfr_plus};
+use egt_on_efg::double_oracle::double_oracle;
 use egt_on_efg::egt::EGT;
-use egt_on_efg::game::Game;
-use egt_on_efg::prox_func::{Centering, Farina2021, Normal};
+use egt_on_efg::experiment::{self, Experiment};
+use egt_on_efg::fictitious_play::xfp;
+use egt_on_efg::frank_wolfe::{away_frank_wolfe, frank_wolfe, SmoothedFW};
+use egt_on_efg::game::{Game, StrategyPolytope};
+use egt_on_efg::logging;
+use egt_on_efg::manifest::{self, Manifest, Status};
+use egt_on_efg::metrics;
+use egt_on_efg::mirror_prox::MirrorProx;
+use egt_on_efg::mmd::MMD;
+use egt_on_efg::omd::OMD;
+use egt_on_efg::pdhg::PDHG;
+use egt_on_efg::plot;
+use egt_on_efg::prox_func::{
+    Centering, DilatedEuclidean, Euclidean, Farina2021, GlobalEntropy, Normal, ProxFunction,
+    WeightScheme,
+};
+use egt_on_efg::qre::QRE;
+use egt_on_efg::result::{SolveResult, State};
+use egt_on_efg::schedule::{self, Phase};
+use egt_on_efg::stop;
 
 use argh::FromArgs;
 use chrono::Local;
+use log::{error, info, LevelFilter};
+use ndarray::Array1;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::io::Write;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[derive(FromArgs)]
-/// Config
+/// Solve two-player zero-sum extensive-form games in the sequence form.
+struct Cli {
+    /// log more: once for the internal values of the solvers and EGT's mu1/mu2 in the
+    /// progress bar, twice for everything
+    #[argh(switch, short = 'v')]
+    verbose: u8,
+
+    /// log only warnings and errors and hide the progress bars
+    #[argh(switch, short = 'q')]
+    quiet: bool,
+
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+#[allow(clippy::large_enum_variant)] // parsed once
+enum Command {
+    Solve(Solve),
+    Eval(Eval),
+    Info(Info),
+    Convert(Convert),
+    Bench(Bench),
+    Generate(Generate),
+    Plot(Plot),
+}
+
+#[derive(FromArgs, Serialize)]
+/// Solve a game and write the strategies and the errors to a run directory.
+#[argh(subcommand, name = "solve")]
+struct Solve {
+    /// filepath of an experiment file (TOML or JSON) giving the game, the method, the
+    /// stopping rule, the output directory and the options; the options given on the
+    /// command line take precedence
+    #[argh(option, short = 'c')]
+    #[serde(skip)]
+    config: Option<String>,
+
+    /// filepath of the game
+    #[argh(option, short = 'g')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    game: Option<String>,
+
+    /// the method, or a schedule of phases `method[key=value,...]:budget` like
+    /// `cfr+:10%,egt[prox=centering]:90%` (budget: N% of --step or N steps;
+    /// handoff=avg|last; the phases after the first must take a warm start)
+    #[argh(option, short = 'm')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+
+    /// the number of iterations
+    #[argh(option, short = 's')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    step: Option<usize>,
+
+    /// the directory to create the run directory in (default: log)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+
+    /// the format of the per-iteration metrics of the run: jsonl or csv (default: jsonl)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<String>,
+
+    /// stop after the iteration running at this many seconds
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_limit: Option<f64>,
+
+    /// the prox function of player 1 for egt and omd: normal, farina, global,
+    /// euclidean, dilated-euclidean, centering (at --init-x) or centering=<path>
+    /// (default: normal)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prox_x: Option<String>,
+
+    /// the prox function of player 2 for egt and omd: normal, farina, global,
+    /// euclidean, dilated-euclidean, centering (at --init-y) or centering=<path>
+    /// (default: normal)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prox_y: Option<String>,
+
+    /// the infoset weights of the dilated prox function of player 1: uniform,
+    /// kroer, depth=<factor>, recursive or file=<path> (default: recursive)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weights_x: Option<String>,
+
+    /// the infoset weights of the dilated prox function of player 2: uniform,
+    /// kroer, depth=<factor>, recursive or file=<path> (default: recursive)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weights_y: Option<String>,
+
+    /// filepath of the initial strategy of player 1 for egt, egt-farina, cfr, cfr+,
+    /// mirror-prox, mmd, omd and komwu: a vector like x.json or a behavioral strategy
+    /// {"<obs>": {"<action>": prob}}; it is the center of egt and omd only with --prox-x centering
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    init_x: Option<String>,
+
+    /// filepath of the initial strategy of player 2 (see --init-x; egt and egt-farina
+    /// only use it as the center of --prox-y centering)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    init_y: Option<String>,
+
+    /// the lower bound of the behavioral strategies of the initial strategies, the magnets
+    /// of mmd and the centers of centering, egt-centering and mix, renormalized at each infoset
+    /// (default: 1e-6)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    center_floor: Option<f64>,
+
+    /// the initial tau of egt (default: 0.5)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tau: Option<f64>,
+
+    /// the factor multiplied to tau of egt after each successful step, growing tau up to
+    /// 0.9 or the initial tau if larger (default: 1.0)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tau_growth: Option<f64>,
+
+    /// the factor multiplied to tau of egt on each failed step (default: 0.5)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tau_shrink: Option<f64>,
+
+    /// rebalance mu1 and mu2 of egt every this number of steps (default: 0, never)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    balance: Option<usize>,
+
+    /// stop egt once its certified bound of the gap is at most this value
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eps: Option<f64>,
+
+    /// evaluate the exact gap of egt only at the first and the last step
+    #[argh(switch)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    skip_error: bool,
+
+    /// the step size (default: 1/max|A_ij| for mirror-prox, pdhg, omd and komwu,
+    /// 0.9/||A||_2 for pdhg-euclid, 0.1 for mmd)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eta: Option<f64>,
+
+    /// adapt the step size of mirror-prox by line search
+    #[argh(switch)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    line_search: bool,
+
+    /// restart pdhg adaptively on the normalized duality gap
+    #[argh(switch)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    restart: bool,
+
+    /// the regularization temperature of mmd (default: 0.05)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alpha: Option<f64>,
+
+    /// the initial smoothing parameter of smoothed-fw (default: 1.0)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mu: Option<f64>,
+
+    /// filepath of the magnet strategy of player 1 for mmd, in the forms of --init-x
+    /// (default: the center)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    magnet_x: Option<String>,
+
+    /// filepath of the magnet strategy of player 2 for mmd (default: the center)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    magnet_y: Option<String>,
+
+    /// the rationality λ of qre (the largest one for qre-trace)
+    /// (default: 1.0)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lambda: Option<f64>,
+
+    /// the number of positive rationalities traced by qre-trace (default: 20)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lambda_num: Option<usize>,
+
+    /// the tolerance of qre on the difference from the logit responses (default: 1e-10)
+    #[argh(option)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tol: Option<f64>,
+}
+
+// The options of `solve` resolved from the defaults, the experiment file and the command line
+#[derive(Clone, Serialize, Deserialize)]
 struct Config {
+    game: String,
+    method: String,
+    step: usize,
+    output: String,
+    metrics: String,
+    time_limit: Option<f64>,
+    prox_x: String,
+    prox_y: String,
+    weights_x: String,
+    weights_y: String,
+    init_x: Option<String>,
+    init_y: Option<String>,
+    center_floor: f64,
+    tau: f64,
+    tau_growth: f64,
+    tau_shrink: f64,
+    balance: usize,
+    eps: Option<f64>,
+    skip_error: bool,
+    eta: Option<f64>,
+    line_search: bool,
+    restart: bool,
+    alpha: f64,
+    mu: f64,
+    magnet_x: Option<String>,
+    magnet_y: Option<String>,
+    lambda: f64,
+    lambda_num: usize,
+    tol: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            game: String::new(),
+            method: String::new(),
+            step: 0,
+            output: String::from("log"),
+            metrics: String::from("jsonl"),
+            time_limit: None,
+            prox_x: String::from("normal"),
+            prox_y: String::from("normal"),
+            weights_x: String::from("recursive"),
+            weights_y: String::from("recursive"),
+            init_x: None,
+            init_y: None,
+            center_floor: 1e-6,
+            tau: 0.5,
+            tau_growth: 1.0,
+            tau_shrink: 0.5,
+            balance: 0,
+            eps: None,
+            skip_error: false,
+            eta: None,
+            line_search: false,
+            restart: false,
+            alpha: 0.05,
+            mu: 1.0,
+            magnet_x: None,
+            magnet_y: None,
+            lambda: 1.0,
+            lambda_num: 20,
+            tol: 1e-10,
+        }
+    }
+}
+
+#[derive(FromArgs)]
+/// Evaluate the exploitability of a strategy profile.
+#[argh(subcommand, name = "eval")]
+struct Eval {
     /// filepath of the game
     #[argh(option, short = 'g')]
     game: String,
 
-    /// the method
+    /// filepath of the strategy of player 1: a vector like x.json or a behavioral
+    /// strategy {"<obs>": {"<action>": prob}}
+    #[argh(option, short = 'x')]
+    x: String,
+
+    /// filepath of the strategy of player 2 (see -x)
+    #[argh(option, short = 'y')]
+    y: String,
+}
+
+#[derive(FromArgs)]
+/// Show the size of a game and the constants of the prox functions with the
+/// implied iteration bounds.
+#[argh(subcommand, name = "info")]
+struct Info {
+    /// filepath of the game
+    #[argh(option, short = 'g')]
+    game: String,
+
+    /// the prox function of player 1 (see solve; default: normal)
+    #[argh(option, default = "String::from(\"normal\")")]
+    prox_x: String,
+
+    /// the prox function of player 2 (see solve; default: normal)
+    #[argh(option, default = "String::from(\"normal\")")]
+    prox_y: String,
+
+    /// the infoset weights of player 1 (see solve; default: recursive)
+    #[argh(option, default = "String::from(\"recursive\")")]
+    weights_x: String,
+
+    /// the infoset weights of player 2 (see solve; default: recursive)
+    #[argh(option, default = "String::from(\"recursive\")")]
+    weights_y: String,
+
+    /// the lower bound of the behavioral strategies of the centers (default: 1e-6)
+    #[argh(option, default = "1e-6")]
+    center_floor: f64,
+
+    /// the target gap of the iteration bounds (default: 1e-3)
+    #[argh(option, default = "1e-3")]
+    eps: f64,
+}
+
+#[derive(FromArgs)]
+/// Convert a strategy between a vector like x.json and a behavioral strategy,
+/// i.e. the probabilities of the actions at each observation.
+#[argh(subcommand, name = "convert")]
+struct Convert {
+    /// filepath of the game
+    #[argh(option, short = 'g')]
+    game: String,
+
+    /// the player of the strategy: 1 or 2 (default: 1)
+    #[argh(option, short = 'p', default = "1")]
+    player: usize,
+
+    /// filepath of the strategy in either form
+    #[argh(option, short = 'i')]
+    input: String,
+
+    /// the form to convert to: vector or labels (default: the other form)
+    #[argh(option)]
+    to: Option<String>,
+
+    /// filepath of the output (default: stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+}
+
+#[derive(FromArgs)]
+/// Run every combination of games, methods and numbers of iterations in parallel and
+/// summarize the results in a table and summary.csv of the bench directory.
+/// Every method is deterministic, so the runs take no seeds.
+#[argh(subcommand, name = "bench")]
+struct Bench {
+    /// filepath of a game (repeatable)
+    #[argh(option, short = 'g')]
+    game: Vec<String>,
+
+    /// a method or a schedule (repeatable)
     #[argh(option, short = 'm')]
-    method: String,
+    method: Vec<String>,
 
-    /// the number of iterations
+    /// the number of iterations (repeatable)
     #[argh(option, short = 's')]
+    step: Vec<usize>,
+
+    /// the number of worker threads (default: the number of CPUs)
+    #[argh(option, short = 'j')]
+    jobs: Option<usize>,
+
+    /// the time limit of each run in seconds
+    #[argh(option)]
+    time_limit: Option<f64>,
+
+    /// the exploitability for the time to reach it (default: 1e-3)
+    #[argh(option, default = "1e-3")]
+    target: f64,
+
+    /// the directory to create the bench directory in (default: log)
+    #[argh(option, default = "String::from(\"log\")")]
+    output: String,
+}
+
+#[derive(FromArgs)]
+/// Draw log-log plots of the errors of runs against the iterations and the wall time.
+#[argh(subcommand, name = "plot")]
+struct Plot {
+    /// run directories to overlay
+    #[argh(positional)]
+    runs: Vec<String>,
+
+    /// the legend of each run in order (default: the run directory)
+    #[argh(option, short = 'l')]
+    label: Vec<String>,
+
+    /// the title of the plot
+    #[argh(option, short = 't', default = "String::new()")]
+    title: String,
+
+    /// filepath of the image: .svg or .png
+    #[argh(option, short = 'o')]
+    output: String,
+}
+
+#[derive(FromArgs)]
+/// Write an experiment file for solve with the default options.
+#[argh(subcommand, name = "generate")]
+struct Generate {
+    /// filepath of the experiment file (.toml or .json)
+    #[argh(positional)]
+    output: String,
+
+    /// filepath of the game
+    #[argh(option, short = 'g')]
+    game: Option<String>,
+
+    /// the method
+    #[argh(option, short = 'm')]
+    method: Option<String>,
+
+    /// the number of iterations (default: 1000)
+    #[argh(option, short = 's', default = "1000")]
     step: usize,
 }
 
-fn main() {
-    let cfg: Config = argh::from_env();
-    let game = Game::load(&cfg.game);
-    let start = std::time::Instant::now();
-    let (x, y, error) = match cfg.method.as_str() {
-        "cfr" => cfr(&game, cfg.step),
-        "cfr+" => cfr_plus(&game, cfg.step),
+// The methods other than schedules
+const METHODS: [&str; 19] = [
+    "cfr",
+    "cfr+",
+    "egt",
+    "egt-farina",
+    "mirror-prox",
+    "xfp",
+    "double-oracle",
+    "fw",
+    "afw",
+    "smoothed-fw",
+    "pdhg",
+    "pdhg-euclid",
+    "omd",
+    "komwu",
+    "mmd",
+    "qre",
+    "qre-trace",
+    "egt-centering",
+    "mix",
+];
+
+// The methods taking the initial strategies of --init-x and --init-y,
+// and those of the previous phase of a schedule
+const WARM_START: [&str; 8] = [
+    "cfr",
+    "cfr+",
+    "egt",
+    "egt-farina",
+    "mirror-prox",
+    "omd",
+    "komwu",
+    "mmd",
+];
+
+// The outputs of the solvers besides `SolveResult`
+#[derive(Default)]
+struct Logs {
+    qre_path: Vec<(f64, Array1<f64>, Array1<f64>)>,
+    bound: Vec<f64>, // concatenated over the phases of a schedule
+}
+
+fn build_egt<'a, PF1: ProxFunction + ?Sized, PF2: ProxFunction + ?Sized>(
+    cfg: &Config,
+    game: &'a Game,
+    pf1: &'a PF1,
+    pf2: &'a PF2,
+) -> EGT<'a, PF1, PF2> {
+    EGT::new(game, pf1, pf2)
+        .aggressive(cfg.tau, cfg.tau_growth, cfg.tau_shrink)
+        .balance(cfg.balance)
+        .certify(cfg.eps, !cfg.skip_error)
+}
+
+fn load_strategy(filepath: &str) -> Result<Array1<f64>, String> {
+    let file_content = std::fs::read_to_string(filepath)
+        .map_err(|e| format!("cannot read `{}`: {}", filepath, e))?;
+    let x = serde_json::from_str::<Vec<f64>>(&file_content)
+        .map_err(|e| format!("invalid `{}`: {}", filepath, e))?;
+    Ok(x.into())
+}
+
+fn load_init(filepath: &str, sp: &StrategyPolytope, floor: f64) -> Result<Array1<f64>, String> {
+    let file_content = std::fs::read_to_string(filepath)
+        .map_err(|e| format!("cannot read `{}`: {}", filepath, e))?;
+    let json = serde_json::from_str::<Value>(&file_content)
+        .map_err(|e| format!("invalid `{}`: {}", filepath, e))?;
+    let x = match json {
+        Value::Array(_) => {
+            let x: Array1<f64> = serde_json::from_value::<Vec<f64>>(json)
+                .map_err(|e| format!("invalid `{}`: {}", filepath, e))?
+                .into();
+            if x.len() != *sp.idx.last().unwrap() {
+                return Err(format!(
+                    "invalid `{}`: the length {} differs from the {} sequences",
+                    filepath,
+                    x.len(),
+                    sp.idx.last().unwrap()
+                ));
+            }
+            if let Some(j) = x.iter().position(|&p| !p.is_finite() || p < 0.0) {
+                return Err(format!("invalid `{}`: x[{}] = {}", filepath, j, x[j]));
+            }
+            x
+        }
+        json => {
+            let strategy = serde_json::from_value(json)
+                .map_err(|e| format!("invalid `{}`: {}", filepath, e))?;
+            (sp.from_labels(&strategy)).map_err(|e| format!("invalid `{}`: {}", filepath, e))?
+        }
+    };
+    Ok(sp.smooth(&x, floor))
+}
+
+fn init_pair(
+    init_x: &Option<Array1<f64>>,
+    init_y: &Option<Array1<f64>>,
+    x0: &Array1<f64>,
+    y0: &Array1<f64>,
+) -> Option<(Array1<f64>, Array1<f64>)> {
+    // Complete the given initial strategies with x0 or y0
+    if init_x.is_none() && init_y.is_none() {
+        return None;
+    }
+    Some((
+        init_x.clone().unwrap_or_else(|| x0.clone()),
+        init_y.clone().unwrap_or_else(|| y0.clone()),
+    ))
+}
+
+const PROX: [&str; 7] = [
+    "normal",
+    "farina",
+    "global",
+    "euclidean",
+    "dilated-euclidean",
+    "centering",
+    "centering=<path>",
+];
+
+fn build_weights(spec: &str) -> Result<WeightScheme, String> {
+    match spec.split_once('=') {
+        None if spec == "uniform" => Ok(WeightScheme::Uniform),
+        None if spec == "kroer" => Ok(WeightScheme::Kroer2020),
+        None if spec == "recursive" => Ok(WeightScheme::Recursive),
+        Some(("depth", factor)) => match factor.parse() {
+            Ok(factor) => Ok(WeightScheme::Depth(factor)),
+            Err(_) => Err(format!("invalid factor `{}` of depth weights", factor)),
+        },
+        Some(("file", path)) => Ok(WeightScheme::Custom(load_strategy(path)?)),
+        _ => Err(format!(
+            "unknown weight scheme `{}` (uniform, kroer, depth=<factor>, recursive, file=<path>)",
+            spec
+        )),
+    }
+}
+
+fn check_prox(spec: &str) -> Result<(), String> {
+    // Check the name of the prox function, whose file is loaded with the game
+    match spec.split_once('=') {
+        None if PROX.contains(&spec) => Ok(()),
+        Some(("centering", _)) => Ok(()),
+        _ => Err(format!(
+            "unknown prox function `{}` ({})",
+            spec,
+            PROX.join(", ")
+        )),
+    }
+}
+
+fn build_prox<'a>(
+    spec: &str,
+    weights: &str,
+    floor: f64,
+    init: &Option<Array1<f64>>,
+    sp: &'a StrategyPolytope,
+) -> Result<Box<dyn ProxFunction + 'a>, String> {
+    check_prox(spec)?;
+    let w = (build_weights(weights)?.weights(sp))
+        .map_err(|e| format!("the weights `{}`: {}", weights, e))?;
+    Ok(match spec.split_once('=') {
+        None if spec == "normal" => Box::new(Normal::with_weights(sp, w)),
+        None if spec == "farina" => Box::new(Farina2021::with_weights(sp, w)),
+        None if spec == "global" => Box::new(GlobalEntropy::new(sp)),
+        None if spec == "euclidean" => Box::new(Euclidean::new(sp)),
+        None if spec == "dilated-euclidean" => Box::new(DilatedEuclidean::with_weights(sp, w)),
+        None => {
+            let center = (init.clone())
+                .ok_or("`centering` needs the initial strategy or a file (centering=<path>)")?;
+            Box::new(Centering::with_weights(sp, center, w))
+        }
+        Some((_, path)) => Box::new(Centering::with_weights(sp, load_init(path, sp, floor)?, w)),
+    })
+}
+
+fn info(args: &Info) {
+    let game = Game::load(&args.game).unwrap_or_else(|e| fail(&e));
+    for (name, sp) in [("player 1", &game.sp1), ("player 2", &game.sp2)] {
+        println!(
+            "{}: {} infosets, {} sequences",
+            name,
+            sp.par.len(),
+            sp.idx.last().unwrap()
+        );
+    }
+    println!(
+        "A: {} nonzeros, ||A||_2 = {:e}",
+        game.mat_a.nnz(),
+        game.spectral_norm()
+    );
+
+    let pf1 = build_prox(
+        &args.prox_x,
+        &args.weights_x,
+        args.center_floor,
+        &None,
+        &game.sp1,
+    )
+    .unwrap_or_else(|e| fail(&e));
+    let pf2 = build_prox(
+        &args.prox_y,
+        &args.weights_y,
+        args.center_floor,
+        &None,
+        &game.sp2,
+    )
+    .unwrap_or_else(|e| fail(&e));
+    let (d1, d2) = (pf1.max(), pf2.max());
+    let (s1, s2) = (pf1.modulus(), pf2.modulus());
+    let l = game.mat_a.max_abs();
+    let eps = args.eps;
+    println!("player 1 ({}): D = {:e}, sigma = {:e}", args.prox_x, d1, s1);
+    println!("player 2 ({}): D = {:e}, sigma = {:e}", args.prox_y, d2, s2);
+    println!("||A||_(1,inf) = {:e}", l);
+    // The gaps after T steps are at most 4||A||sqrt(D1D2/(s1s2))/T for EGT and
+    // 2||A||sqrt(D1D2/(s1s2))/T for Mirror Prox with the optimally scaled prox function.
+    if s1 == 0.0 || s2 == 0.0 {
+        // e.g. the dilated entropy with uniform weights, whose modulus has no positive bound
+        println!("EGT, Mirror Prox: no bound (sigma = 0 with these weights)");
+        return;
+    }
+    let c = l * (d1 * d2 / (s1 * s2)).sqrt();
+    println!(
+        "EGT: {:.0} steps for eps = {:e}",
+        (4.0 * c / eps).ceil(),
+        eps
+    );
+    println!(
+        "Mirror Prox: {:.0} steps for eps = {:e}",
+        (2.0 * c / eps).ceil(),
+        eps
+    );
+}
+
+fn eval(args: &Eval) {
+    let game = Game::load(&args.game).unwrap_or_else(|e| fail(&e));
+    let x = load_init(&args.x, &game.sp1, 0.0).unwrap_or_else(|e| fail(&e));
+    let y = load_init(&args.y, &game.sp2, 0.0).unwrap_or_else(|e| fail(&e));
+    let min = game.sp1.minimize(game.mat_a.dot(&y));
+    let max = game.sp2.maximize(game.mat_a_t.dot(&x));
+    println!("value = {:e}", x.dot(&game.mat_a.dot(&y)));
+    println!("best response of player 1 = {:e}", min);
+    println!("best response of player 2 = {:e}", max);
+    println!("exploitability = {:e}", max - min);
+}
+
+fn convert(args: &Convert) {
+    let game = Game::load(&args.game).unwrap_or_else(|e| fail(&e));
+    let sp = match args.player {
+        1 => &game.sp1,
+        2 => &game.sp2,
+        _ => fail("the player must be 1 or 2"),
+    };
+    let x = load_init(&args.input, sp, 0.0).unwrap_or_else(|e| fail(&e));
+    // `load_init` has read the file as JSON
+    let file_content = std::fs::read_to_string(&args.input).unwrap();
+    let is_vector = serde_json::from_str::<Value>(&file_content)
+        .unwrap()
+        .is_array();
+    let to = args
+        .to
+        .clone()
+        .unwrap_or_else(|| String::from(if is_vector { "labels" } else { "vector" }));
+    let content = match to.as_str() {
+        "vector" => serde_json::to_string(&x.to_vec()).unwrap(),
+        "labels" => {
+            if sp.obs.is_empty() {
+                fail(&format!("`{}` has no labels of the infosets", args.game));
+            }
+            serde_json::to_string_pretty(&sp.to_labels(&x)).unwrap()
+        }
+        _ => fail(&format!("unknown form `{}` (vector, labels)", to)),
+    };
+    match &args.output {
+        Some(path) => std::fs::write(path, content + "\n").unwrap(),
+        None => println!("{}", content),
+    }
+}
+
+fn quote(field: &str) -> String {
+    // Quote a field of CSV, which may contain commas and quotes
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+fn bench(args: &Bench) {
+    if args.game.is_empty() || args.method.is_empty() || args.step.is_empty() {
+        fail("bench needs at least one each of -g, -m and -s");
+    }
+    let games: Vec<Game> = (args.game.iter())
+        .map(|path| Game::load(path).unwrap_or_else(|e| fail(&e)))
+        .collect();
+
+    let mut jobs = vec![];
+    for g in 0..games.len() {
+        for method in &args.method {
+            for &step in &args.step {
+                let cfg = Config {
+                    game: args.game[g].clone(),
+                    method: method.clone(),
+                    step,
+                    time_limit: args.time_limit,
+                    ..Default::default()
+                };
+                check_method(&cfg).unwrap_or_else(|e| fail(&e));
+                jobs.push((g, cfg));
+            }
+        }
+    }
+    let dirname =
+        manifest::create_run_dir(&args.output, "bench", Local::now()).unwrap_or_else(|e| fail(&e));
+    for (_, cfg) in &mut jobs {
+        cfg.output = dirname.clone();
+    }
+    let threads = args
+        .jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    // Append each row to summary.csv as soon as its job finishes, so that the rows of
+    // the finished jobs survive a later failure; the jobs after an interruption are not run.
+    let mut file = std::fs::File::create(format!("{}/summary.csv", dirname)).unwrap();
+    writeln!(
+        file,
+        "game,method,step,status,iterations,error,time_to_target,iters_per_sec,time"
+    )
+    .unwrap();
+    println!(
+        "{:<16} {:<32} {:>11} {:>8} {:>14} {:>12} {:>12} {:>10}",
+        "game", "method", "status", "iters", "error", "to target[s]", "iters/s", "time[s]"
+    );
+    let summary = Mutex::new(file);
+    let record = |cfg: &Config, outcome: Option<Outcome>| {
+        let game = manifest::run_name(&cfg.game, "");
+        let game = game.trim_end_matches('-');
+        let mut file = summary.lock().unwrap();
+        let Some(outcome) = outcome else {
+            println!("{:<16} {:<32} {:>11}", game, cfg.method, "failed");
+            writeln!(
+                file,
+                "{},{},{},failed,,,,,",
+                quote(&cfg.game),
+                quote(&cfg.method),
+                cfg.step
+            )
+            .unwrap();
+            return;
+        };
+        let to_target = outcome.reached.map(|(_, time)| time);
+        let rate = outcome.iterations as f64 / outcome.time;
+        println!(
+            "{:<16} {:<32} {:>11} {:>8} {:>14.6e} {:>12} {:>12.1} {:>10.3}",
+            game,
+            cfg.method,
+            outcome.status.name(),
+            outcome.iterations,
+            outcome.error,
+            to_target.map_or(String::from("-"), |t| format!("{:.3}", t)),
+            rate,
+            outcome.time
+        );
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{}",
+            quote(&cfg.game),
+            quote(&cfg.method),
+            cfg.step,
+            outcome.status.name(),
+            outcome.iterations,
+            outcome.error,
+            to_target.map_or(String::new(), |t| t.to_string()),
+            rate,
+            outcome.time
+        )
+        .unwrap();
+    };
+    let next = AtomicUsize::new(0);
+    let cooperation = stop::cooperate();
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, jobs.len()) {
+            scope.spawn(|| loop {
+                if stop::interrupted() {
+                    break;
+                }
+                let k = next.fetch_add(1, Ordering::Relaxed);
+                let Some((g, cfg)) = jobs.get(k) else {
+                    break;
+                };
+                // A panicking job is recorded as failed instead of taking the others down
+                let outcome = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                    execute(cfg, &games[*g], Some(args.target))
+                }));
+                let outcome = match outcome {
+                    Ok(Ok(outcome)) => Some(outcome),
+                    Ok(Err(e)) => {
+                        error!("{} on {}: {}", cfg.method, cfg.game, e);
+                        None
+                    }
+                    Err(_) => {
+                        stop::set_deadline(None);
+                        metrics::uninstall();
+                        error!("{} on {} panicked", cfg.method, cfg.game);
+                        None
+                    }
+                };
+                record(cfg, outcome);
+            });
+        }
+    });
+    drop(cooperation);
+    println!("written to {}", dirname);
+}
+
+fn plot(args: &Plot) {
+    if args.runs.is_empty() {
+        fail("no run directory is given");
+    }
+    if args.label.len() > args.runs.len() {
+        fail("more labels than run directories are given");
+    }
+    let curves: Vec<plot::Curve> = (args.runs.iter().enumerate())
+        .map(|(i, run)| {
+            let label = args.label.get(i).unwrap_or(run);
+            plot::Curve::load(run.trim_end_matches('/'), label).unwrap_or_else(|e| fail(&e))
+        })
+        .collect();
+    plot::draw(&curves, &args.title, &args.output).unwrap_or_else(|e| fail(&e));
+}
+
+fn generate(args: &Generate) {
+    let mut cfg = Config::default();
+    if let Some(method) = &args.method {
+        cfg.method = method.clone();
+        check_method(&cfg).unwrap_or_else(|e| fail(&e));
+    }
+    let mut options = match serde_json::to_value(&cfg).unwrap() {
+        Value::Object(options) => options,
+        _ => unreachable!(),
+    };
+    options.retain(|key, value| {
+        !value.is_null() && !["game", "method", "step", "output"].contains(&key.as_str())
+    });
+    let experiment = Experiment {
+        game: args.game.clone(),
+        method: args.method.clone(),
+        stop: experiment::Stop {
+            step: Some(args.step),
+            eps: None,
+        },
+        output: experiment::Output {
+            dir: Some(cfg.output),
+        },
+        options,
+    };
+    experiment.save(&args.output).unwrap_or_else(|e| fail(&e));
+}
+
+fn run_method(
+    cfg: &Config,
+    game: &Game,
+    init_x: &Option<Array1<f64>>,
+    init_y: &Option<Array1<f64>>,
+    logs: &mut Logs,
+) -> Result<SolveResult, String> {
+    let result = match cfg.method.as_str() {
+        "cfr" | "cfr+" => {
+            let init = init_pair(init_x, init_y, &game.sp1.uniform(), &game.sp2.uniform());
+            let init = init.as_ref().map(|(x, y)| (x, y));
+            if cfg.method == "cfr" {
+                cfr(game, cfg.step, init)
+            } else {
+                cfr_plus(game, cfg.step, init)
+            }
+        }
         "egt" => {
-            let pf1 = Normal::new(&game.sp1);
-            let pf2 = Normal::new(&game.sp2);
-            let egt = EGT::new(&game, &pf1, &pf2);
-            egt.run(cfg.step)
+            let pf1 = build_prox(
+                &cfg.prox_x,
+                &cfg.weights_x,
+                cfg.center_floor,
+                init_x,
+                &game.sp1,
+            )?;
+            let pf2 = build_prox(
+                &cfg.prox_y,
+                &cfg.weights_y,
+                cfg.center_floor,
+                init_y,
+                &game.sp2,
+            )?;
+            let mut egt = build_egt(cfg, game, &*pf1, &*pf2);
+            if let Some(x0) = init_x {
+                egt = egt.start(x0.clone());
+            }
+            egt.run(cfg.step)?
         }
         "egt-farina" => {
             let pf1 = Farina2021::new(&game.sp1);
             let pf2 = Farina2021::new(&game.sp2);
-            let egt = EGT::new(&game, &pf1, &pf2);
-            egt.run(cfg.step)
+            let mut egt = build_egt(cfg, game, &pf1, &pf2);
+            if let Some(x0) = init_x {
+                egt = egt.start(x0.clone());
+            }
+            egt.run(cfg.step)?
+        }
+        "mirror-prox" => {
+            let pf1 = Normal::new(&game.sp1);
+            let pf2 = Normal::new(&game.sp2);
+            let eta = cfg.eta.unwrap_or(1.0 / game.mat_a.max_abs());
+            let mut mp = MirrorProx::new(game, &pf1, &pf2, eta, cfg.line_search);
+            if let Some((x, y)) = init_pair(init_x, init_y, pf1.center(), pf2.center()) {
+                mp = mp.init(x, y);
+            }
+            mp.run(cfg.step)
         }
-        "egt-centering" => {
+        "xfp" => xfp(game, cfg.step),
+        "double-oracle" => double_oracle(game, cfg.step),
+        "fw" => frank_wolfe(game, cfg.step),
+        "afw" => away_frank_wolfe(game, cfg.step),
+        "smoothed-fw" => {
             let pf1 = Normal::new(&game.sp1);
             let pf2 = Normal::new(&game.sp2);
-            let egt = EGT::new(&game, &pf1, &pf2);
-            let (x, y, mut error) = egt.run(cfg.step / 10);
-
-            let pf1 = Centering::new(&game.sp1, x);
-            let pf2 = Centering::new(&game.sp2, y);
-            let egt = EGT::new(&game, &pf1, &pf2);
-            let (x, y, mut error2) = egt.run(cfg.step * 9 / 10);
-            error.append(&mut error2);
-            (x, y, error)
-        }
-        "mix" => {
-            let (x, y, mut error) = cfr_plus(&game, cfg.step / 10);
-            let pf1 = Centering::new(&game.sp1, x);
-            let pf2 = Centering::new(&game.sp2, y);
-            let egt = EGT::new(&game, &pf1, &pf2);
-            let (x, y, mut error2) = egt.run(cfg.step * 9 / 10);
-            error.append(&mut error2);
-            (x, y, error)
-        }
-        _ => panic!(),
+            let fw = SmoothedFW::new(game, &pf1, &pf2, cfg.mu);
+            fw.run(cfg.step)
+        }
+        "pdhg" => {
+            let pf1 = Normal::new(&game.sp1);
+            let pf2 = Normal::new(&game.sp2);
+            let eta = cfg.eta.unwrap_or(1.0 / game.mat_a.max_abs());
+            let pdhg = PDHG::new(game, &pf1, &pf2, eta, eta, cfg.restart);
+            pdhg.run(cfg.step)
+        }
+        "pdhg-euclid" => {
+            let pf1 = Euclidean::new(&game.sp1);
+            let pf2 = Euclidean::new(&game.sp2);
+            let eta = cfg.eta.unwrap_or(0.9 / game.spectral_norm());
+            let pdhg = PDHG::new(game, &pf1, &pf2, eta, eta, cfg.restart);
+            pdhg.run(cfg.step)
+        }
+        "omd" => {
+            let pf1 = build_prox(
+                &cfg.prox_x,
+                &cfg.weights_x,
+                cfg.center_floor,
+                init_x,
+                &game.sp1,
+            )?;
+            let pf2 = build_prox(
+                &cfg.prox_y,
+                &cfg.weights_y,
+                cfg.center_floor,
+                init_y,
+                &game.sp2,
+            )?;
+            let eta = cfg.eta.unwrap_or(1.0 / game.mat_a.max_abs());
+            let mut omd = OMD::new(game, &*pf1, &*pf2, eta);
+            if let Some((x, y)) = init_pair(init_x, init_y, pf1.center(), pf2.center()) {
+                omd = omd.init(x, y);
+            }
+            omd.run(cfg.step)
+        }
+        "komwu" => {
+            let pf1 = GlobalEntropy::new(&game.sp1);
+            let pf2 = GlobalEntropy::new(&game.sp2);
+            let eta = cfg.eta.unwrap_or(1.0 / game.mat_a.max_abs());
+            let mut omd = OMD::new(game, &pf1, &pf2, eta);
+            if let Some((x, y)) = init_pair(init_x, init_y, pf1.center(), pf2.center()) {
+                omd = omd.init(x, y);
+            }
+            omd.run(cfg.step)
+        }
+        "mmd" => {
+            let pf1 = Normal::new(&game.sp1);
+            let pf2 = Normal::new(&game.sp2);
+            let eta = cfg.eta.unwrap_or(0.1);
+            let mut mmd = MMD::new(game, &pf1, &pf2, cfg.alpha, eta);
+            if cfg.magnet_x.is_some() || cfg.magnet_y.is_some() {
+                // Smoothed like the initial strategies, since MMD stalls at a magnet with zeros
+                let magnet1 = match &cfg.magnet_x {
+                    Some(path) => load_init(path, &game.sp1, cfg.center_floor)?,
+                    None => pf1.center().clone(),
+                };
+                let magnet2 = match &cfg.magnet_y {
+                    Some(path) => load_init(path, &game.sp2, cfg.center_floor)?,
+                    None => pf2.center().clone(),
+                };
+                mmd = mmd.magnet(magnet1, magnet2);
+            }
+            if let Some((x, y)) = init_pair(init_x, init_y, pf1.center(), pf2.center()) {
+                mmd = mmd.init(x, y);
+            }
+            mmd.run(cfg.step)
+        }
+        "qre" => {
+            let qre = QRE::new(game, cfg.tol)?;
+            qre.solve(cfg.lambda, cfg.step)
+        }
+        "qre-trace" => {
+            let qre = QRE::new(game, cfg.tol)?;
+            // λ = 0 and λ_max * 10^{-3}, ..., λ_max
+            let lambdas: Vec<f64> = std::iter::once(0.0)
+                .chain((0..cfg.lambda_num).map(|k| {
+                    let t = (k + 1) as f64 / cfg.lambda_num as f64;
+                    cfg.lambda * 10f64.powf(3.0 * (t - 1.0))
+                }))
+                .collect();
+            let start = Instant::now();
+            logs.qre_path = qre.trace(&lambdas, cfg.step);
+            let error = (logs.qre_path.iter().enumerate())
+                .map(|(k, (_, x, y))| (k, game.error(x, y)))
+                .collect();
+            let (_, x, y) = logs.qre_path.last().unwrap().clone();
+            SolveResult::new(x, y, error, start)
+        }
+        _ => panic!(
+            "unknown method `{}` ({} or a schedule like `cfr+:10%,egt[prox=centering]:90%`)",
+            cfg.method,
+            METHODS.join(", ")
+        ),
     };
-    let end = start.elapsed();
-    println!(
-        "{}.{:03}[s] elapsed.",
-        end.as_secs(),
-        end.subsec_nanos() / 1_000_000
-    );
-    dbg!(x.dot(&game.mat_a.dot(&y)));
+    if let State::Egt { bound, .. } = &result.state {
+        logs.bound.extend(bound);
+    }
+    Ok(result)
+}
+
+fn set_option(cfg: &mut Config, key: &str, value: Value) -> Result<(), String> {
+    // Override an option of `cfg` by a phase of a schedule or an experiment file,
+    // where `prox` and `weights` set those of both players.
+    let key = key.replace('-', "_");
+    if key == "prox" || key == "weights" {
+        set_option(cfg, &format!("{}_x", key), value.clone())?;
+        return set_option(cfg, &format!("{}_y", key), value);
+    }
+    let mut json = serde_json::to_value(&*cfg).unwrap();
+    let options = json.as_object_mut().unwrap();
+    if !options.contains_key(&key) {
+        let mut keys: Vec<&str> = options.keys().map(|k| k.as_str()).collect();
+        keys.extend(["prox", "weights"]);
+        return Err(format!("unknown option `{}` ({})", key, keys.join(", ")));
+    }
+    options.insert(key.clone(), value);
+    *cfg = serde_json::from_value(json)
+        .map_err(|e| format!("invalid value for the option `{}`: {}", key, e))?;
+    Ok(())
+}
 
-    let now = Local::now().format("%Y%m%d-%H:%M").to_string();
-    let dirname = format!("log/{}-{}-{}", now, cfg.game, cfg.method);
-    std::fs::create_dir_all(&dirname).unwrap();
+fn parse_value(value: &str) -> Value {
+    // Read `1e-3` and `true` as JSON, and anything else as a string
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
 
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    // Exit as by the signal if the error came of an interruption
+    std::process::exit(stop::exit_code().unwrap_or(2));
+}
+
+fn expand_method(method: &str) -> &str {
+    // Return the schedule of a hybrid method
+    match method {
+        "egt-centering" => "egt:10%,egt[prox=centering]:90%",
+        "mix" => "cfr+:10%,egt[prox=centering]:90%",
+        method => method,
+    }
+}
+
+fn phase_config(cfg: &Config, phase: &Phase, step: usize) -> Result<Config, String> {
+    // Return `cfg` for a phase of a schedule running `step` steps
+    let mut phase_cfg = cfg.clone();
+    phase_cfg.method = phase.method.clone();
+    phase_cfg.step = step;
+    for (key, value) in &phase.options {
+        set_option(&mut phase_cfg, key, parse_value(value))
+            .map_err(|e| format!("phase `{}`: {}", phase.method, e))?;
+    }
+    Ok(phase_cfg)
+}
+
+fn phase_configs(cfg: &Config) -> Result<Vec<Config>, String> {
+    // Return the configs of the phases of `cfg`, or `cfg` itself if it is not a schedule
+    let method = expand_method(&cfg.method);
+    if !schedule::is_schedule(method) {
+        return Ok(vec![cfg.clone()]);
+    }
+    let phases = schedule::parse(method)?;
+    let steps = schedule::steps(&phases, cfg.step);
+    (phases.iter().zip(steps))
+        .map(|(phase, step)| phase_config(cfg, phase, step))
+        .collect()
+}
+
+fn check_options(cfg: &Config) -> Result<(), String> {
+    // Check the ranges of the options that the solvers assert
+    if !(0.0 < cfg.tau && cfg.tau < 1.0) {
+        return Err(format!("--tau must be in (0, 1), not {}", cfg.tau));
+    }
+    if cfg.tau_growth < 1.0 {
+        return Err(format!(
+            "--tau-growth must be at least 1, not {}",
+            cfg.tau_growth
+        ));
+    }
+    if !(0.0 < cfg.tau_shrink && cfg.tau_shrink < 1.0) {
+        return Err(format!(
+            "--tau-shrink must be in (0, 1), not {}",
+            cfg.tau_shrink
+        ));
+    }
+    check_prox(&cfg.prox_x).map_err(|e| format!("--prox-x: {}", e))?;
+    check_prox(&cfg.prox_y).map_err(|e| format!("--prox-y: {}", e))?;
+    build_weights(&cfg.weights_x).map_err(|e| format!("--weights-x: {}", e))?;
+    build_weights(&cfg.weights_y).map_err(|e| format!("--weights-y: {}", e))?;
+    Ok(())
+}
+
+fn check_init(cfg: &Config) -> Result<(), String> {
+    // Check that the method of `cfg` uses the given --init-x and --init-y
+    let method = cfg.method.as_str();
+    if (cfg.init_x.is_some() || cfg.init_y.is_some()) && !WARM_START.contains(&method) {
+        return Err(format!(
+            "`{}` takes no initial strategy (--init-x and --init-y are for {})",
+            method,
+            WARM_START.join(", ")
+        ));
+    }
+    let centering = cfg.prox_y == "centering";
+    if cfg.init_y.is_some() && (method == "egt-farina" || method == "egt" && !centering) {
+        return Err(format!(
+            "`{}` starts from --init-x and uses --init-y only as the center of --prox-y centering",
+            method
+        ));
+    }
+    Ok(())
+}
+
+fn check_method(cfg: &Config) -> Result<(), String> {
+    // Check the method of `cfg` and the options of each phase if it is a schedule
+    let schedule = schedule::is_schedule(expand_method(&cfg.method));
+    let configs = phase_configs(cfg)?;
+    for (p, cfg) in configs.iter().enumerate() {
+        if !METHODS.contains(&cfg.method.as_str()) {
+            return Err(format!(
+                "unknown method `{}` ({} or a schedule like `cfr+:10%,egt[prox=centering]:90%`)",
+                cfg.method,
+                METHODS.join(", ")
+            ));
+        }
+        // The initial strategies go to the first phase of a schedule
+        // and the strategies of each phase to the next one
+        let init = if p == 0 {
+            check_init(cfg)
+        } else if !WARM_START.contains(&cfg.method.as_str()) {
+            Err(format!(
+                "`{}` cannot start from the previous phase (only {} can)",
+                cfg.method,
+                WARM_START.join(", ")
+            ))
+        } else {
+            Ok(())
+        };
+        init.and_then(|_| check_options(cfg))
+            .map_err(|e| match schedule {
+                true => format!("phase `{}`: {}", cfg.method, e),
+                false => e,
+            })?;
+    }
+    Ok(())
+}
+
+fn check_prox_files(
+    cfg: &Config,
+    game: &Game,
+    init_x: &Option<Array1<f64>>,
+    init_y: &Option<Array1<f64>>,
+) -> Result<(), String> {
+    // Build the prox functions of each phase with the game to report the errors of their files
+    // and centers before the run directory is created
+    for (p, cfg) in phase_configs(cfg)?.iter().enumerate() {
+        if !["egt", "omd"].contains(&cfg.method.as_str()) {
+            continue;
+        }
+        // The later phases start from the strategies of the previous one
+        let (init_x, init_y) = match p {
+            0 => (init_x.clone(), init_y.clone()),
+            _ => (Some(game.sp1.uniform()), Some(game.sp2.uniform())),
+        };
+        let prox = |spec, weights, init, sp| build_prox(spec, weights, cfg.center_floor, init, sp);
+        prox(&cfg.prox_x, &cfg.weights_x, &init_x, &game.sp1)
+            .map_err(|e| format!("--prox-x: {}", e))?;
+        prox(&cfg.prox_y, &cfg.weights_y, &init_y, &game.sp2)
+            .map_err(|e| format!("--prox-y: {}", e))?;
+    }
+    Ok(())
+}
+
+fn load_config(args: &Solve) -> Result<Config, String> {
+    // Merge the defaults, the experiment file and the options given on the command line
+    // in this order of precedence, and validate the result
+    let mut cfg = Config::default();
+    if let Some(path) = &args.config {
+        let experiment = Experiment::load(path)?;
+        for (key, value) in experiment.options {
+            set_option(&mut cfg, &key, value).map_err(|e| format!("{}: {}", path, e))?;
+        }
+        cfg.game = experiment.game.unwrap_or(cfg.game);
+        cfg.method = experiment.method.unwrap_or(cfg.method);
+        cfg.step = experiment.stop.step.unwrap_or(cfg.step);
+        cfg.eps = experiment.stop.eps.or(cfg.eps);
+        cfg.output = experiment.output.dir.unwrap_or(cfg.output);
+    }
+    // Only the options given are serialized
+    for (key, value) in serde_json::to_value(args).unwrap().as_object().unwrap() {
+        set_option(&mut cfg, key, value.clone())?;
+    }
+    if cfg.game.is_empty() {
+        return Err("no game is given (-g or `game` in the experiment file)".to_string());
+    }
+    if cfg.method.is_empty() {
+        return Err(format!(
+            "no method is given (-m or `method` in the experiment file): {}",
+            METHODS.join(", ")
+        ));
+    }
+    if cfg.step == 0 {
+        return Err("no positive number of steps is given (-s or `step` in [stop])".to_string());
+    }
+    if !["jsonl", "csv"].contains(&cfg.metrics.as_str()) {
+        return Err(format!(
+            "unknown metrics format `{}` (jsonl, csv)",
+            cfg.metrics
+        ));
+    }
+    check_method(&cfg)?;
+    Ok(cfg)
+}
+
+fn run_schedule(
+    cfg: &Config,
+    game: &Game,
+    phases: &[Phase],
+    init_x: &Option<Array1<f64>>,
+    init_y: &Option<Array1<f64>>,
+    logs: &mut Logs,
+) -> Result<SolveResult, String> {
+    // Return the result of the last phase with the errors and the time of all the phases
+    let mut init_x = init_x.clone();
+    let mut init_y = init_y.clone();
+    let mut result: Option<SolveResult> = None;
+    let mut error = vec![];
+    let mut time = Duration::ZERO;
+    for (phase, step) in phases.iter().zip(schedule::steps(phases, cfg.step)) {
+        let phase_cfg = phase_config(cfg, phase, step)?;
+        info!("phase {} for {} steps", phase.method, phase_cfg.step);
+        // Number the iterations of the phase after those of the previous phases
+        let offset = error.last().map_or(0, |&(k, _): &(usize, f64)| k + 1);
+        metrics::set_offset(offset);
+        let phase_result = run_method(&phase_cfg, game, &init_x, &init_y, logs)?;
+        error.extend(phase_result.error.iter().map(|&(k, e)| (offset + k, e)));
+        time += phase_result.time;
+        let (x, y) = phase.handoff.strategies(&phase_result);
+        init_x = Some(game.sp1.smooth(x, cfg.center_floor));
+        init_y = Some(game.sp2.smooth(y, cfg.center_floor));
+        result = Some(phase_result);
+        if stop::requested() {
+            break;
+        }
+    }
+    let mut result = result.expect("the schedule has no phase");
+    result.error = error;
+    result.time = time;
+    Ok(result)
+}
+
+#[derive(Clone)]
+struct Outcome {
+    status: Status,
+    iterations: usize,
+    error: f64,
+    time: f64,                     // in seconds
+    reached: Option<(usize, f64)>, // the first iteration and time with error <= target
+}
+
+fn solve(cfg: Config) {
+    let game = Game::load(&cfg.game).unwrap_or_else(|e| fail(&e));
+    let _cooperation = stop::cooperate();
+    execute(&cfg, &game, None).unwrap_or_else(|e| fail(&e));
+}
+
+fn execute(cfg: &Config, game: &Game, target: Option<f64>) -> Result<Outcome, String> {
+    // Run `cfg` in a new run directory of `cfg.output`
+    let init_x = (cfg.init_x.as_ref())
+        .map(|path| load_init(path, &game.sp1, cfg.center_floor))
+        .transpose()?;
+    let init_y = (cfg.init_y.as_ref())
+        .map(|path| load_init(path, &game.sp2, cfg.center_floor))
+        .transpose()?;
+    check_prox_files(cfg, game, &init_x, &init_y)?;
+    let now = Local::now();
+    let name = manifest::run_name(&cfg.game, &cfg.method);
+    let dirname = manifest::create_run_dir(&cfg.output, &name, now)?;
+    let manifest = Manifest::new(serde_json::to_value(cfg).unwrap(), &cfg.game, now);
+    let mut manifest = manifest::Guard::new(manifest, &dirname);
+    let sink = metrics::Sink::create(&format!("{}/metrics.{}", &dirname, cfg.metrics))?;
+    metrics::install(match target {
+        Some(eps) => sink.target(eps),
+        None => sink,
+    });
+
+    let start = std::time::Instant::now();
+    stop::set_deadline(cfg.time_limit.map(|t| start + Duration::from_secs_f64(t)));
+    let mut logs = Logs::default();
+    let method = expand_method(&cfg.method);
+    let result = if schedule::is_schedule(method) {
+        let phases = schedule::parse(method)?;
+        run_schedule(cfg, game, &phases, &init_x, &init_y, &mut logs)
+    } else {
+        run_method(cfg, game, &init_x, &init_y, &mut logs)
+    };
+    let result = result.inspect_err(|_| {
+        stop::set_deadline(None);
+        metrics::uninstall();
+    })?;
+    let end = start.elapsed();
+    let timed_out = stop::timed_out();
+    stop::set_deadline(None);
+    let reached = metrics::uninstall().and_then(|sink| sink.reached());
+    info!("{}.{:03}[s] elapsed.", end.as_secs(), end.subsec_millis());
+    let value = result.x.dot(&game.mat_a.dot(&result.y));
+    info!("value = {:e}", value);
+    let status = if stop::interrupted() {
+        Status::Interrupted
+    } else if timed_out {
+        Status::TimeLimited
+    } else {
+        Status::Completed
+    };
+    let outcome = Outcome {
+        status,
+        iterations: result.iterations(),
+        error: result.final_error(),
+        time: end.as_secs_f64(),
+        reached,
+    };
+    // The pairs [iteration, error] of the evaluated iterations
     let mut file = std::fs::File::create(format!("{}/error.json", &dirname)).unwrap();
-    writeln!(file, "{}", format!("{:?}", &error)).unwrap();
+    writeln!(file, "{}", serde_json::to_string(&result.error).unwrap()).unwrap();
 
     let mut file = std::fs::File::create(format!("{}/x.json", &dirname)).unwrap();
-    writeln!(file, "{}", serde_json::to_string(&x.to_vec()).unwrap()).unwrap();
+    writeln!(
+        file,
+        "{}",
+        serde_json::to_string(&result.x.to_vec()).unwrap()
+    )
+    .unwrap();
     let mut file = std::fs::File::create(format!("{}/y.json", &dirname)).unwrap();
-    writeln!(file, "{}", serde_json::to_string(&y.to_vec()).unwrap()).unwrap();
+    writeln!(
+        file,
+        "{}",
+        serde_json::to_string(&result.y.to_vec()).unwrap()
+    )
+    .unwrap();
+
+    // The last iterates of the averaging solvers
+    if let Some((x, y)) = &result.last {
+        let mut file = std::fs::File::create(format!("{}/x_last.json", &dirname)).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&x.to_vec()).unwrap()).unwrap();
+        let mut file = std::fs::File::create(format!("{}/y_last.json", &dirname)).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&y.to_vec()).unwrap()).unwrap();
+    }
+
+    if !logs.bound.is_empty() {
+        let mut file = std::fs::File::create(format!("{}/bound.json", &dirname)).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&logs.bound).unwrap()).unwrap();
+    }
+
+    if let State::DoubleOracle { support } = &result.state {
+        let mut file = std::fs::File::create(format!("{}/support.json", &dirname)).unwrap();
+        writeln!(file, "{}", serde_json::to_string(support).unwrap()).unwrap();
+    }
+
+    if !logs.qre_path.is_empty() {
+        let mut file = std::fs::File::create(format!("{}/qre.jsonl", &dirname)).unwrap();
+        for (lambda, x, y) in &logs.qre_path {
+            let obj = serde_json::json!({"lambda": lambda, "x": x.to_vec(), "y": y.to_vec()});
+            writeln!(file, "{}", obj).unwrap();
+        }
+    }
+
+    // Finish run.json last so that a finished run has all its outputs
+    let metrics = manifest::Metrics {
+        iterations: outcome.iterations,
+        error: outcome.error,
+        value,
+    };
+    manifest.finish(status, Local::now(), metrics);
+    Ok(outcome)
+}
+
+fn main() {
+    let cli: Cli = argh::from_env();
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    logging::init(level, cli.quiet);
+    stop::handle_signals();
+    match cli.command {
+        Command::Solve(args) => solve(load_config(&args).unwrap_or_else(|e| fail(&e))),
+        Command::Eval(args) => eval(&args),
+        Command::Info(args) => info(&args),
+        Command::Convert(args) => convert(&args),
+        Command::Bench(args) => bench(&args),
+        Command::Generate(args) => generate(&args),
+        Command::Plot(args) => plot(&args),
+    }
+    if let Some(code) = stop::exit_code() {
+        std::process::exit(code);
+    }
 }
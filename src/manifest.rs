@@ -0,0 +1,236 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+// The manifest `run.json` of a run directory, from which the run can be reproduced:
+// it is written when the run starts and rewritten with the end and the final metrics.
+#[derive(Serialize, Debug)]
+pub struct Manifest {
+    pub status: Status,
+    pub config: Value,
+    pub version: String,
+    pub game: GameInfo,
+    pub host: Host,
+    pub start: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed: Option<f64>, // in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<Metrics>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Running,
+    Completed,
+    #[serde(rename = "time-limited")]
+    TimeLimited, // by --time-limit, with the results up to the last iteration
+    Interrupted, // by SIGINT or SIGTERM, with the results up to the last iteration
+    Failed,      // by an error or a panic, without the results
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::TimeLimited => "time-limited",
+            Self::Interrupted => "interrupted",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct GameInfo {
+    pub path: String,
+    pub sha256: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Host {
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Metrics {
+    pub iterations: usize, // the iterations run, i.e. the last evaluated iteration + 1
+    pub error: f64,
+    pub value: f64,
+}
+
+fn sha256(filepath: &str) -> String {
+    let content = std::fs::read(filepath).unwrap();
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl Host {
+    pub fn current() -> Self {
+        Self {
+            hostname: gethostname::gethostname().to_string_lossy().into_owned(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn new(config: Value, game_path: &str, start: DateTime<Local>) -> Self {
+        Self {
+            status: Status::Running,
+            config,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            game: GameInfo {
+                path: game_path.to_string(),
+                sha256: sha256(game_path),
+            },
+            host: Host::current(),
+            start: start.to_rfc3339(),
+            end: None,
+            elapsed: None,
+            metrics: None,
+        }
+    }
+    pub fn finish(&mut self, status: Status, end: DateTime<Local>, metrics: Metrics) {
+        let start = DateTime::parse_from_rfc3339(&self.start).unwrap();
+        self.status = status;
+        let elapsed = end.fixed_offset() - start;
+        self.elapsed = Some(elapsed.num_microseconds().unwrap() as f64 / 1e6);
+        self.end = Some(end.to_rfc3339());
+        self.metrics = Some(metrics);
+    }
+    pub fn save(&self, dirname: &str) {
+        let content = serde_json::to_string_pretty(self).unwrap();
+        std::fs::write(format!("{}/run.json", dirname), content + "\n").unwrap();
+    }
+}
+
+// The manifest of a run in progress, which is saved as failed if it is dropped
+// while still running, e.g. by an error or a panic during the run.
+pub struct Guard {
+    pub manifest: Manifest,
+    dirname: String,
+}
+impl Guard {
+    pub fn new(manifest: Manifest, dirname: &str) -> Self {
+        manifest.save(dirname);
+        Self {
+            manifest,
+            dirname: dirname.to_string(),
+        }
+    }
+    pub fn finish(&mut self, status: Status, end: DateTime<Local>, metrics: Metrics) {
+        self.manifest.finish(status, end, metrics);
+        self.manifest.save(&self.dirname);
+    }
+}
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if self.manifest.status != Status::Running {
+            return;
+        }
+        self.manifest.status = Status::Failed;
+        self.manifest.end = Some(Local::now().to_rfc3339());
+        // Do not panic while unwinding
+        if let Ok(content) = serde_json::to_string_pretty(&self.manifest) {
+            let _ = std::fs::write(format!("{}/run.json", self.dirname), content + "\n");
+        }
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    // Keep a name as a single path component, e.g. `cfr+:10%,egt[prox=centering]:90%`
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '+' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+pub fn run_name(game_path: &str, method: &str) -> String {
+    // Return `{game}-{method}` with the file stem of the game
+    let game = std::path::Path::new(game_path)
+        .file_stem()
+        .map_or(String::from("game"), |s| s.to_string_lossy().into_owned());
+    format!("{}-{}", game, method)
+}
+
+pub fn create_run_dir(output: &str, name: &str, start: DateTime<Local>) -> Result<String, String> {
+    // Create `{output}/{time}-{name}`, suffixed with `-2`, `-3`, ... if it already exists
+    std::fs::create_dir_all(output).map_err(|e| format!("cannot create `{}`: {}", output, e))?;
+    let base = format!(
+        "{}/{}-{}",
+        output,
+        start.format("%Y%m%d-%H%M%S"),
+        sanitize(name)
+    );
+    for k in 1.. {
+        let dirname = if k == 1 {
+            base.clone()
+        } else {
+            format!("{}-{}", base, k)
+        };
+        match std::fs::create_dir(&dirname) {
+            Ok(()) => return Ok(dirname),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("cannot create `{}`: {}", dirname, e)),
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_keeps_one_component() {
+        assert_eq!(
+            sanitize("cfr+:10%,egt[prox=centering]:90%"),
+            "cfr+_10__egt_prox_centering__90_"
+        );
+        assert_eq!(sanitize("../kuhn-egt_1.5"), ".._kuhn-egt_1.5");
+        assert_eq!(run_name("games/leduc.json", "cfr+"), "leduc-cfr+");
+    }
+
+    #[test]
+    fn create_run_dir_suffixes_repeats() {
+        let output =
+            std::env::temp_dir().join(format!("egt-on-efg-manifest-{}", std::process::id()));
+        let output = output.to_str().unwrap();
+        let start = Local::now();
+        let first = create_run_dir(output, "kuhn-egt", start).unwrap();
+        let second = create_run_dir(output, "kuhn-egt", start).unwrap();
+        let third = create_run_dir(output, "kuhn-egt", start).unwrap();
+        std::fs::remove_dir_all(output).unwrap();
+        let base = format!("{}/{}-kuhn-egt", output, start.format("%Y%m%d-%H%M%S"));
+        assert_eq!(first, base);
+        assert_eq!(second, format!("{}-2", base));
+        assert_eq!(third, format!("{}-3", base));
+        assert!(!first[output.len()..].contains(':'));
+    }
+
+    #[test]
+    fn status_names_match_run_json() {
+        for status in [
+            Status::Running,
+            Status::Completed,
+            Status::TimeLimited,
+            Status::Interrupted,
+            Status::Failed,
+        ] {
+            assert_eq!(serde_json::to_value(status).unwrap(), status.name());
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::game::Game;
+use crate::logging;
+use ndarray::Array1;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+// A sink of one record per evaluated iteration, in JSON Lines
+//
+//     {"iteration":10,"time":0.012,"error":0.0031,"value":-0.0856,"mu1":0.02,...}
+//
+// or in CSV with the solver-specific columns of `FIELDS` left empty when absent.
+// The sink is installed per thread, so the solvers record their internal state without
+// taking it as an argument, and every record is flushed so partial runs remain usable.
+
+// The solver-specific fields:
+// mu1, mu2, tau and bound (= mu1*D1 + mu2*D2) of EGT and
+// regret (the sum of the positive counterfactual regrets over the infosets per step) of CFR and
+// regularized_gap (the duality gap of the regularized game) of MMD.
+pub const FIELDS: [&str; 6] = ["mu1", "mu2", "tau", "bound", "regret", "regularized_gap"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Jsonl,
+    Csv,
+}
+
+pub struct Sink {
+    writer: BufWriter<File>,
+    format: Format,
+    start: Instant,
+    offset: usize,
+    target: Option<f64>,
+    reached: Option<(usize, f64)>, // the first iteration and time with error <= target
+}
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+impl Format {
+    pub fn from_path(filepath: &str) -> Self {
+        if filepath.ends_with(".csv") {
+            Format::Csv
+        } else {
+            Format::Jsonl
+        }
+    }
+}
+
+impl Sink {
+    pub fn create(filepath: &str) -> Result<Self, String> {
+        let file =
+            File::create(filepath).map_err(|e| format!("cannot create `{}`: {}", filepath, e))?;
+        let format = Format::from_path(filepath);
+        let mut writer = BufWriter::new(file);
+        if format == Format::Csv {
+            writeln!(writer, "iteration,time,error,value,{}", FIELDS.join(",")).unwrap();
+        }
+        Ok(Self {
+            writer,
+            format,
+            start: Instant::now(),
+            offset: 0,
+            target: None,
+            reached: None,
+        })
+    }
+    pub fn target(mut self, eps: f64) -> Self {
+        self.target = Some(eps);
+        self
+    }
+    pub fn reached(&self) -> Option<(usize, f64)> {
+        self.reached
+    }
+    fn write(&mut self, iteration: usize, error: f64, value: f64, fields: &[(&str, f64)]) {
+        let iteration = self.offset + iteration;
+        let time = self.start.elapsed().as_secs_f64();
+        if self.reached.is_none() && self.target.is_some_and(|eps| error <= eps) {
+            self.reached = Some((iteration, time));
+        }
+        match self.format {
+            Format::Jsonl => {
+                let mut obj = serde_json::json!({
+                    "iteration": iteration,
+                    "time": time,
+                    "error": error,
+                    "value": value,
+                });
+                for &(key, v) in fields {
+                    obj[key] = serde_json::json!(v);
+                }
+                writeln!(self.writer, "{}", obj).unwrap();
+            }
+            Format::Csv => {
+                let columns: Vec<String> = FIELDS
+                    .iter()
+                    .map(|key| match fields.iter().find(|(k, _)| k == key) {
+                        Some((_, v)) => v.to_string(),
+                        None => String::new(),
+                    })
+                    .collect();
+                writeln!(
+                    self.writer,
+                    "{},{},{},{},{}",
+                    iteration,
+                    time,
+                    error,
+                    value,
+                    columns.join(",")
+                )
+                .unwrap();
+            }
+        }
+        self.writer.flush().unwrap();
+    }
+}
+
+pub fn install(sink: Sink) {
+    SINK.with(|s| *s.borrow_mut() = Some(sink));
+}
+
+pub fn uninstall() -> Option<Sink> {
+    SINK.with(|s| s.borrow_mut().take())
+}
+
+pub fn set_offset(offset: usize) {
+    // Number the following iterations from `offset`, e.g. for the phases of a schedule
+    SINK.with(|s| {
+        if let Some(sink) = s.borrow_mut().as_mut() {
+            sink.offset = offset;
+        }
+    });
+}
+
+pub fn record(iteration: usize, error: f64, value: f64, fields: &[(&str, f64)]) {
+    debug_assert!(fields.iter().all(|(key, _)| FIELDS.contains(key)));
+    logging::report(error, fields);
+    SINK.with(|s| {
+        if let Some(sink) = s.borrow_mut().as_mut() {
+            sink.write(iteration, error, value, fields);
+        }
+    });
+}
+
+pub fn observe(
+    iteration: usize,
+    game: &Game,
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    fields: &[(&str, f64)],
+) -> f64 {
+    // Record the error and the value of (x, y) and return the error
+    let (error, value) = game.evaluate(x, y);
+    record(iteration, error, value, fields);
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_records(filepath: &str) -> Option<(usize, f64)> {
+        install(Sink::create(filepath).unwrap().target(0.5));
+        record(0, 1.0, -0.25, &[("mu1", 0.5), ("tau", 0.1)]);
+        set_offset(10);
+        record(1, 0.25, -0.125, &[("regret", 2.0)]);
+        uninstall().unwrap().reached()
+    }
+
+    #[test]
+    fn jsonl_records() {
+        let filepath =
+            std::env::temp_dir().join(format!("egt-on-efg-metrics-{}.jsonl", std::process::id()));
+        let filepath = filepath.to_str().unwrap();
+        let reached = write_records(filepath);
+        let contents = std::fs::read_to_string(filepath).unwrap();
+        std::fs::remove_file(filepath).unwrap();
+        let records: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["iteration"], 0);
+        assert_eq!(records[0]["error"], 1.0);
+        assert_eq!(records[0]["value"], -0.25);
+        assert_eq!(records[0]["mu1"], 0.5);
+        assert_eq!(records[0]["tau"], 0.1);
+        assert!(records[0].get("regret").is_none());
+        assert_eq!(records[1]["iteration"], 11);
+        assert_eq!(records[1]["regret"], 2.0);
+        assert_eq!(reached.unwrap().0, 11);
+    }
+
+    #[test]
+    fn csv_records() {
+        let filepath =
+            std::env::temp_dir().join(format!("egt-on-efg-metrics-{}.csv", std::process::id()));
+        let filepath = filepath.to_str().unwrap();
+        write_records(filepath);
+        let contents = std::fs::read_to_string(filepath).unwrap();
+        std::fs::remove_file(filepath).unwrap();
+        let rows: Vec<Vec<&str>> = contents
+            .lines()
+            .map(|line| line.split(',').collect())
+            .collect();
+        assert_eq!(
+            rows[0],
+            [
+                "iteration",
+                "time",
+                "error",
+                "value",
+                "mu1",
+                "mu2",
+                "tau",
+                "bound",
+                "regret",
+                "regularized_gap"
+            ]
+        );
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()));
+        assert_eq!([rows[1][0], rows[1][2], rows[1][3]], ["0", "1", "-0.25"]);
+        assert_eq!(&rows[1][4..], ["0.5", "", "0.1", "", "", ""]);
+        assert_eq!(rows[2][0], "11");
+        assert_eq!(&rows[2][4..], ["", "", "", "", "2", ""]);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::game::Game;
+use crate::logging;
+use crate::metrics;
+use crate::prox_func::ProxFunction;
+use crate::result::{SolveResult, State, Weighting};
+use crate::stop;
+use log::{debug, info, warn};
+use ndarray::Array1;
+use std::time::Instant;
+
+pub struct MirrorProx<'a, PF: ProxFunction> {
+    game: &'a Game,
+    pf1: &'a PF,
+    pf2: &'a PF,
+    eta: f64,
+    line_search: bool,
+    init: Option<(Array1<f64>, Array1<f64>)>,
+}
+impl<'a, PF: ProxFunction> MirrorProx<'a, PF> {
+    pub fn new(game: &'a Game, pf1: &'a PF, pf2: &'a PF, eta: f64, line_search: bool) -> Self {
+        Self {
+            game,
+            pf1,
+            pf2,
+            eta,
+            line_search,
+            init: None,
+        }
+    }
+    pub fn init(mut self, x: Array1<f64>, y: Array1<f64>) -> Self {
+        self.init = Some((x, y));
+        self
+    }
+    fn step(
+        &self,
+        x: &Array1<f64>,
+        y: &Array1<f64>,
+        gx: &Array1<f64>,
+        gy: &Array1<f64>,
+        eta: f64,
+    ) -> (Array1<f64>, Array1<f64>) {
+        // gx = Ay, gy = -A^Tx
+        let x_nxt = self.pf1.projection(x.clone(), eta * gx);
+        let y_nxt = self.pf2.projection(y.clone(), eta * gy);
+        (x_nxt, y_nxt)
+    }
+    fn bregman(&self, x: &Array1<f64>, y: &Array1<f64>, u: &Array1<f64>, v: &Array1<f64>) -> f64 {
+        // Return D((x, y), (u, v))
+        self.pf1.bregman(x.clone(), u.clone()) + self.pf2.bregman(y.clone(), v.clone())
+    }
+    pub fn run(&self, step: usize) -> SolveResult {
+        let start = Instant::now();
+        let (mut x, mut y) = match &self.init {
+            Some((x, y)) => (x.clone(), y.clone()),
+            None => (self.pf1.center().clone(), self.pf2.center().clone()),
+        };
+        let mut eta = self.eta;
+        debug!("initial eta = {:e}", eta);
+
+        let mut gx = self.game.mat_a.dot(&y);
+        let mut gy = -self.game.mat_a_t.dot(&x);
+        let mut sum_x = Array1::<f64>::zeros(x.len());
+        let mut sum_y = Array1::<f64>::zeros(y.len());
+        let mut sum_eta = 0.0;
+
+        let mut error = vec![(0, metrics::observe(0, self.game, &x, &y, &[]))];
+        debug!("initial error = {:e}", error[0].1);
+
+        for k in logging::progress(1..step) {
+            if stop::requested() {
+                break;
+            }
+            let nxt = loop {
+                let (x_half, y_half) = self.step(&x, &y, &gx, &gy, eta);
+                let gx_half = self.game.mat_a.dot(&y_half);
+                let gy_half = -self.game.mat_a_t.dot(&x_half);
+                let (x_nxt, y_nxt) = self.step(&x, &y, &gx_half, &gy_half, eta);
+                if !self.line_search {
+                    break Some((x_half, y_half, x_nxt, y_nxt));
+                }
+                // Accept if η<F(w)-F(z), w-z'> <= D(w, z) + D(z', w) up to rounding errors
+                let lhs = eta
+                    * ((gx_half - &gx).dot(&(&x_half - &x_nxt))
+                        + (gy_half - &gy).dot(&(&y_half - &y_nxt)));
+                let rhs = self.bregman(&x_half, &y_half, &x, &y)
+                    + self.bregman(&x_nxt, &y_nxt, &x_half, &y_half);
+                if lhs <= rhs + 1e-12 {
+                    break Some((x_half, y_half, x_nxt, y_nxt));
+                }
+                eta *= 0.5;
+                if eta <= 1e-20 {
+                    break None;
+                }
+            };
+            let Some((x_half, y_half, x_nxt, y_nxt)) = nxt else {
+                // Stop with the iterates so far, as the step size cannot get any smaller
+                warn!("eta underflowed in the line search at step {}", k);
+                break;
+            };
+            sum_x += &(eta * &x_half);
+            sum_y += &(eta * &y_half);
+            sum_eta += eta;
+            x = x_nxt;
+            y = y_nxt;
+            gx = self.game.mat_a.dot(&y);
+            gy = -self.game.mat_a_t.dot(&x);
+            if self.line_search {
+                eta *= 1.2;
+            }
+            error.push((
+                k,
+                metrics::observe(k, self.game, &(&sum_x / sum_eta), &(&sum_y / sum_eta), &[]),
+            ));
+        }
+        debug!("final eta = {:e}", eta);
+        info!("final error = {:e}", error.last().unwrap().1);
+        let result = SolveResult::new(x, y, error, start).state(State::MirrorProx { eta });
+        if sum_eta == 0.0 {
+            return result;
+        }
+        result.average(Weighting::StepSize, sum_x / sum_eta, sum_y / sum_eta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prox_func::Normal;
+
+    #[test]
+    fn converges_on_kuhn() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let pf1 = Normal::new(&game.sp1);
+        let pf2 = Normal::new(&game.sp2);
+        let eta = 1.0 / game.mat_a.max_abs();
+        for line_search in [false, true] {
+            let result = MirrorProx::new(&game, &pf1, &pf2, eta, line_search).run(1000);
+            assert_eq!(result.weighting, Weighting::StepSize);
+            // The gap of the average decreases as O(1/T)
+            let error = |k: usize| result.error[k].1;
+            assert!(
+                error(999) < error(99) / 5.0,
+                "{} vs {}",
+                error(999),
+                error(99)
+            );
+            assert!(error(999) < 1e-2, "error = {:e}", error(999));
+        }
+    }
+}
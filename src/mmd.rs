@@ -0,0 +1,99 @@
+use crate::game::Game;
+use crate::logging;
+use crate::metrics;
+use crate::prox_func::ProxFunction;
+use crate::result::SolveResult;
+use crate::stop;
+use log::{debug, info};
+use ndarray::Array1;
+use std::time::Instant;
+
+pub struct MMD<'a, PF: ProxFunction> {
+    game: &'a Game,
+    pf1: &'a PF,
+    pf2: &'a PF,
+    magnet1: Array1<f64>,
+    magnet2: Array1<f64>,
+    init: Option<(Array1<f64>, Array1<f64>)>,
+    alpha: f64,
+    eta: f64,
+}
+impl<'a, PF: ProxFunction> MMD<'a, PF> {
+    pub fn new(game: &'a Game, pf1: &'a PF, pf2: &'a PF, alpha: f64, eta: f64) -> Self {
+        Self {
+            game,
+            pf1,
+            pf2,
+            magnet1: pf1.center().clone(),
+            magnet2: pf2.center().clone(),
+            init: None,
+            alpha,
+            eta,
+        }
+    }
+    pub fn magnet(mut self, magnet1: Array1<f64>, magnet2: Array1<f64>) -> Self {
+        self.magnet1 = magnet1;
+        self.magnet2 = magnet2;
+        self
+    }
+    pub fn init(mut self, x: Array1<f64>, y: Array1<f64>) -> Self {
+        self.init = Some((x, y));
+        self
+    }
+    fn best_response(&self, pf: &PF, magnet: &Array1<f64>, s: Array1<f64>) -> f64 {
+        // Return max_x <s,x> - αD(x,magnet)
+        let g = pf.grad(magnet.clone());
+        let offset = pf.value(magnet.clone()) - g.dot(magnet);
+        self.alpha * (pf.conj(s / self.alpha + &g) + offset)
+    }
+    pub fn regularized_gap(&self, x: &Array1<f64>, y: &Array1<f64>) -> f64 {
+        // Return the duality gap of min_x max_y x^TAy + αD(x,magnet1) - αD(y,magnet2)
+        let max = self.best_response(self.pf2, &self.magnet2, self.game.mat_a_t.dot(x))
+            + self.alpha * self.pf1.bregman(x.clone(), self.magnet1.clone());
+        let min = -self.best_response(self.pf1, &self.magnet1, -self.game.mat_a.dot(y))
+            - self.alpha * self.pf2.bregman(y.clone(), self.magnet2.clone());
+        max - min
+    }
+    fn descent(
+        &self,
+        pf: &PF,
+        magnet: &Array1<f64>,
+        x: Array1<f64>,
+        g: Array1<f64>,
+    ) -> Array1<f64> {
+        // Return argmin_z η<g,z> + ηαD(z,magnet) + D(z,x)
+        let a = self.eta * self.alpha;
+        pf.conj_grad((pf.grad(x) + a * pf.grad(magnet.clone()) - self.eta * g) / (1.0 + a))
+    }
+    pub fn step(&self, x: Array1<f64>, y: Array1<f64>) -> (Array1<f64>, Array1<f64>) {
+        let gx = self.game.mat_a.dot(&y);
+        let gy = -self.game.mat_a_t.dot(&x);
+        (
+            self.descent(self.pf1, &self.magnet1, x, gx),
+            self.descent(self.pf2, &self.magnet2, y, gy),
+        )
+    }
+    pub fn run(&self, step: usize) -> SolveResult {
+        let start = Instant::now();
+        let (mut x, mut y) = match &self.init {
+            Some((x, y)) => (x.clone(), y.clone()),
+            None => (self.magnet1.clone(), self.magnet2.clone()),
+        };
+
+        let fields =
+            |x: &Array1<f64>, y: &Array1<f64>| [("regularized_gap", self.regularized_gap(x, y))];
+        let mut error = vec![(0, metrics::observe(0, self.game, &x, &y, &fields(&x, &y)))];
+        debug!("initial error = {:e}", error[0].1);
+
+        for k in logging::progress(1..step) {
+            if stop::requested() {
+                break;
+            }
+            (x, y) = self.step(x, y);
+            error.push((k, metrics::observe(k, self.game, &x, &y, &fields(&x, &y))));
+        }
+        debug!("final regularized gap = {:e}", self.regularized_gap(&x, &y));
+        info!("final error = {:e}", error.last().unwrap().1);
+        SolveResult::new(x, y, error, start)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::game::Game;
+use crate::logging;
+use crate::metrics;
+use crate::prox_func::ProxFunction;
+use crate::result::{SolveResult, Weighting};
+use crate::stop;
+use log::{debug, info};
+use ndarray::Array1;
+use std::time::Instant;
+
+// Optimistic mirror descent, which is KOMWU with `GlobalEntropy`
+pub struct OMD<'a, PF1: ProxFunction + ?Sized, PF2: ProxFunction + ?Sized> {
+    game: &'a Game,
+    pf1: &'a PF1,
+    pf2: &'a PF2,
+    eta: f64,
+    init: Option<(Array1<f64>, Array1<f64>)>,
+}
+impl<'a, PF1: ProxFunction + ?Sized, PF2: ProxFunction + ?Sized> OMD<'a, PF1, PF2> {
+    pub fn new(game: &'a Game, pf1: &'a PF1, pf2: &'a PF2, eta: f64) -> Self {
+        Self {
+            game,
+            pf1,
+            pf2,
+            eta,
+            init: None,
+        }
+    }
+    pub fn init(mut self, x: Array1<f64>, y: Array1<f64>) -> Self {
+        self.init = Some((x, y));
+        self
+    }
+    pub fn run(&self, step: usize) -> SolveResult {
+        // x_t = argmin_x η<m_t,x> + D(x,z_t), z_{t+1} = argmin_z η<g_t,z> + D(z,z_t),
+        // where the prediction m_t is the previous gradient g_{t-1}.
+        let (mut zx, mut zy) = match &self.init {
+            Some((x, y)) => (x.clone(), y.clone()),
+            None => (self.pf1.center().clone(), self.pf2.center().clone()),
+        };
+        let mut mx = self.game.mat_a.dot(&zy);
+        let mut my = -self.game.mat_a_t.dot(&zx);
+        let mut sum_x = Array1::<f64>::zeros(zx.len());
+        let mut sum_y = Array1::<f64>::zeros(zy.len());
+        let (mut last_x, mut last_y) = (zx.clone(), zy.clone());
+
+        let start = Instant::now();
+        let mut error = vec![(0, metrics::observe(0, self.game, &zx, &zy, &[]))];
+        debug!("initial error = {:e}", error[0].1);
+
+        for k in logging::progress(1..step) {
+            if stop::requested() {
+                break;
+            }
+            let x = self.pf1.projection(zx.clone(), self.eta * &mx);
+            let y = self.pf2.projection(zy.clone(), self.eta * &my);
+            mx = self.game.mat_a.dot(&y);
+            my = -self.game.mat_a_t.dot(&x);
+            zx = self.pf1.projection(zx, self.eta * &mx);
+            zy = self.pf2.projection(zy, self.eta * &my);
+            sum_x += &x;
+            sum_y += &y;
+            error.push((
+                k,
+                metrics::observe(
+                    k,
+                    self.game,
+                    &(&sum_x / k as f64),
+                    &(&sum_y / k as f64),
+                    &[],
+                ),
+            ));
+            (last_x, last_y) = (x, y);
+        }
+        info!("final error = {:e}", error.last().unwrap().1);
+        let step = error.len(); // less than the given step if stopped
+        let result = SolveResult::new(last_x, last_y, error, start);
+        if step == 1 {
+            return result;
+        }
+        let k = (step - 1) as f64;
+        result.average(Weighting::Uniform, sum_x / k, sum_y / k)
+    }
+}
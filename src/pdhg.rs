@@ -0,0 +1,216 @@
+use crate::game::Game;
+use crate::logging;
+use crate::metrics;
+use crate::prox_func::ProxFunction;
+use crate::result::{SolveResult, State, Weighting};
+use crate::stop;
+use log::{debug, info};
+use ndarray::Array1;
+use std::time::Instant;
+
+// The period of the evaluation of the restart criteria
+const RESTART_CHECK: usize = 64;
+
+pub struct PDHG<'a, PF: ProxFunction> {
+    game: &'a Game,
+    pf1: &'a PF,
+    pf2: &'a PF,
+    tau: f64,
+    sigma: f64,
+    restart: bool,
+}
+impl<'a, PF: ProxFunction> PDHG<'a, PF> {
+    pub fn new(
+        game: &'a Game,
+        pf1: &'a PF,
+        pf2: &'a PF,
+        tau: f64,
+        sigma: f64,
+        restart: bool,
+    ) -> Self {
+        Self {
+            game,
+            pf1,
+            pf2,
+            tau,
+            sigma,
+            restart,
+        }
+    }
+    fn localized_gap(&self, x: &Array1<f64>, y: &Array1<f64>, r: f64) -> f64 {
+        // Return max {x^TAŷ - x̂^TAy : D(x̂,x) + D(ŷ,y) <= r^2/2}, the duality gap localized
+        // to the ball of radius r around (x, y). Its maximizer is the prox step
+        // (x̂, ŷ) = (P_x(t*Ay), P_y(-t*A^Tx)) with the step t putting it on the boundary,
+        // which is found by bisection on log t.
+        let ay = self.game.mat_a.dot(y);
+        let atx = self.game.mat_a_t.dot(x);
+        let step = |t: f64| {
+            let x_hat = self.pf1.projection(x.clone(), t * &ay);
+            let y_hat = self.pf2.projection(y.clone(), -t * &atx);
+            let gap = atx.dot(&y_hat) - ay.dot(&x_hat);
+            let d = self.pf1.bregman(x_hat, x.clone()) + self.pf2.bregman(y_hat, y.clone());
+            (gap, d)
+        };
+        let radius = r * r / 2.0;
+        // The prox steps up to t = lo are in the ball and the one at t = hi is not
+        let (mut lo, mut hi) = (0.0, 1.0);
+        let mut gap = 0.0;
+        loop {
+            let (g, d) = step(hi);
+            if d > radius {
+                break;
+            }
+            (lo, hi, gap) = (hi, 2.0 * hi, g);
+            if hi > 1e12 {
+                return gap;
+            }
+        }
+        for _ in 0..40 {
+            let mid = if lo > 0.0 { (lo * hi).sqrt() } else { hi / 2.0 };
+            let (g, d) = step(mid);
+            if d <= radius {
+                (lo, gap) = (mid, g);
+            } else {
+                hi = mid;
+            }
+            if hi - lo <= 1e-3 * hi {
+                break;
+            }
+        }
+        gap
+    }
+    fn normalized_gap(
+        &self,
+        x: &Array1<f64>,
+        y: &Array1<f64>,
+        x0: &Array1<f64>,
+        y0: &Array1<f64>,
+    ) -> f64 {
+        // Return the normalized duality gap of Applegate et al. (2021), i.e., the gap
+        // localized to the ball of radius r = sqrt(2D((x,y),(x0,y0))) divided by r
+        let d = self.pf1.bregman(x.clone(), x0.clone()) + self.pf2.bregman(y.clone(), y0.clone());
+        let r = (2.0 * d).sqrt();
+        if r > 0.0 {
+            self.localized_gap(x, y, r) / r
+        } else {
+            f64::INFINITY
+        }
+    }
+    pub fn run(&self, step: usize) -> SolveResult {
+        let start = Instant::now();
+        let mut x = self.pf1.center().clone();
+        let mut y = self.pf2.center().clone();
+        let mut sum_x = Array1::<f64>::zeros(x.len());
+        let mut sum_y = Array1::<f64>::zeros(y.len());
+        let mut cnt = 0;
+
+        // The start of the current restart period and its normalized gap
+        let mut x0 = x.clone();
+        let mut y0 = y.clone();
+        let mut mu0 = f64::INFINITY;
+        let mut mu_prev = f64::INFINITY;
+        let mut x_out = x.clone();
+        let mut y_out = y.clone();
+        let mut restarts = 0;
+        let mut artificial = 0;
+
+        let mut error = vec![(0, metrics::observe(0, self.game, &x, &y, &[]))];
+        debug!("initial error = {:e}", error[0].1);
+
+        for k in logging::progress(1..step) {
+            if stop::requested() {
+                break;
+            }
+            let x_nxt = self
+                .pf1
+                .projection(x.clone(), self.tau * self.game.mat_a.dot(&y));
+            let y_nxt = self.pf2.projection(
+                y.clone(),
+                -self.sigma * self.game.mat_a_t.dot(&(2.0 * &x_nxt - &x)),
+            );
+            x = x_nxt;
+            y = y_nxt;
+            sum_x += &x;
+            sum_y += &y;
+            cnt += 1;
+            let avg_x = &sum_x / cnt as f64;
+            let avg_y = &sum_y / cnt as f64;
+            // Adaptive restart of Applegate et al. (2021) on the normalized gap,
+            // evaluated every `RESTART_CHECK` iterations as in PDLP
+            if !self.restart || k % RESTART_CHECK != 0 {
+                error.push((k, metrics::observe(k, self.game, &avg_x, &avg_y, &[])));
+                (x_out, y_out) = (avg_x, avg_y);
+                continue;
+            }
+            let mu_avg = self.normalized_gap(&avg_x, &avg_y, &x0, &y0);
+            let mu_cur = self.normalized_gap(&x, &y, &x0, &y0);
+            let mu = if mu_avg < mu_cur {
+                (x_out, y_out) = (avg_x, avg_y);
+                mu_avg
+            } else {
+                (x_out, y_out) = (x.clone(), y.clone());
+                mu_cur
+            };
+            error.push((k, metrics::observe(k, self.game, &x_out, &y_out, &[])));
+            let sufficient = mu <= 0.2 * mu0;
+            let necessary = mu <= 0.8 * mu0 && mu > mu_prev;
+            if sufficient || necessary || cnt as f64 >= 0.36 * k as f64 {
+                if !sufficient && !necessary {
+                    artificial += 1;
+                }
+                x = x_out.clone();
+                y = y_out.clone();
+                x0 = x.clone();
+                y0 = y.clone();
+                sum_x.fill(0.0);
+                sum_y.fill(0.0);
+                cnt = 0;
+                mu0 = mu;
+                mu_prev = f64::INFINITY;
+                restarts += 1;
+            } else {
+                mu_prev = mu;
+            }
+        }
+        debug!("{} restarts ({} artificial)", restarts, artificial);
+        info!("final error = {:e}", error.last().unwrap().1);
+        let weighting = if self.restart {
+            Weighting::Restarted
+        } else {
+            Weighting::Uniform
+        };
+        SolveResult::new(x, y, error, start)
+            .average(weighting, x_out, y_out)
+            .state(State::Pdhg {
+                restarts,
+                artificial,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prox_func::Normal;
+
+    #[test]
+    fn restart_on_gap() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let pf1 = Normal::new(&game.sp1);
+        let pf2 = Normal::new(&game.sp2);
+        let eta = 1.0 / game.mat_a.max_abs();
+        let result = PDHG::new(&game, &pf1, &pf2, eta, eta, true).run(1000);
+        let State::Pdhg {
+            restarts,
+            artificial,
+        } = result.state
+        else {
+            panic!("no restart state");
+        };
+        assert!(
+            restarts > artificial,
+            "{} restarts, all artificial",
+            restarts
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use serde_json::Value;
+
+// Log-log plots of the exploitability of runs against the iterations and the wall time,
+// side by side, rendered to SVG or PNG by the extension of the output.
+
+const COLOR: [RGBColor; 7] = [
+    RGBColor(0xE6, 0x9F, 0x00),
+    RGBColor(0x56, 0xB4, 0xE9),
+    RGBColor(0x00, 0x9E, 0x73),
+    RGBColor(0x00, 0x72, 0xB2),
+    RGBColor(0xD5, 0x5E, 0x00),
+    RGBColor(0xCC, 0x79, 0xA7),
+    RGBColor(0xF0, 0xE4, 0x42),
+];
+
+pub struct Curve {
+    pub label: String,
+    pub iteration: Vec<f64>,
+    pub time: Option<Vec<f64>>, // absent for runs with only error.json
+    pub error: Vec<f64>,
+}
+
+impl Curve {
+    pub fn load(dirname: &str, label: &str) -> Result<Self, String> {
+        // Read metrics.jsonl or metrics.csv of a run directory, or error.json of older runs
+        let read = |name: &str| std::fs::read_to_string(format!("{}/{}", dirname, name)).ok();
+        let invalid = |name: &str| format!("invalid `{}/{}`", dirname, name);
+        let mut curve = Self {
+            label: label.to_string(),
+            iteration: vec![],
+            time: Some(vec![]),
+            error: vec![],
+        };
+        let mut push = |iteration: f64, time: f64, error: f64| {
+            curve.iteration.push(iteration);
+            curve.time.as_mut().unwrap().push(time);
+            curve.error.push(error);
+        };
+        if let Some(content) = read("metrics.jsonl") {
+            for line in content.lines().filter(|line| !line.is_empty()) {
+                let record: Value =
+                    serde_json::from_str(line).map_err(|_| invalid("metrics.jsonl"))?;
+                let field =
+                    |key: &str| record[key].as_f64().ok_or_else(|| invalid("metrics.jsonl"));
+                push(field("iteration")?, field("time")?, field("error")?);
+            }
+        } else if let Some(content) = read("metrics.csv") {
+            for line in content.lines().skip(1).filter(|line| !line.is_empty()) {
+                let columns: Vec<f64> = line
+                    .split(',')
+                    .take(3)
+                    .map(|v| v.parse().map_err(|_| invalid("metrics.csv")))
+                    .collect::<Result<_, _>>()?;
+                push(columns[0], columns[1], columns[2]);
+            }
+        } else if let Some(content) = read("error.json") {
+            // The pairs [iteration, error], or the errors of every iteration in older runs
+            let pairs: Vec<(usize, f64)> = match serde_json::from_str::<Vec<f64>>(&content) {
+                Ok(error) => error.into_iter().enumerate().collect(),
+                Err(_) => serde_json::from_str(&content).map_err(|_| invalid("error.json"))?,
+            };
+            curve.iteration = pairs.iter().map(|&(k, _)| k as f64).collect();
+            curve.time = None;
+            curve.error = pairs.iter().map(|&(_, e)| e).collect();
+        } else {
+            return Err(format!("`{}` has no metrics nor error.json", dirname));
+        }
+        Ok(curve)
+    }
+}
+
+fn range(values: impl Iterator<Item = f64>) -> std::ops::Range<f64> {
+    // The range of the positive values, which are the ones on log scales
+    let (lo, hi) = values
+        .filter(|&v| v > 0.0 && v.is_finite())
+        .fold((f64::INFINITY, 0.0f64), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if lo > hi {
+        return 1.0..10.0;
+    }
+    lo..hi.max(lo * 10.0)
+}
+
+fn panel<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    curves: &[Curve],
+    x_label: &str,
+    xs: impl Fn(&Curve) -> Option<&Vec<f64>>,
+) -> Result<(), String> {
+    let points = |curve: &Curve| -> Vec<(f64, f64)> {
+        xs(curve).map_or(vec![], |x| {
+            x.iter()
+                .zip(&curve.error)
+                .map(|(&x, &e)| (x, e))
+                .filter(|&(x, e)| x > 0.0 && e > 0.0)
+                .collect()
+        })
+    };
+    let x_range = range(curves.iter().flat_map(&points).map(|(x, _)| x));
+    let y_range = range(curves.iter().flat_map(&points).map(|(_, e)| e));
+    let mut chart = ChartBuilder::on(area)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_range.log_scale(), y_range.log_scale())
+        .map_err(|e| e.to_string())?;
+    chart
+        .configure_mesh()
+        .x_desc(x_label)
+        .y_desc("Error")
+        .x_label_formatter(&|v| format!("{:.0e}", v))
+        .y_label_formatter(&|v| format!("{:.0e}", v))
+        .draw()
+        .map_err(|e| e.to_string())?;
+    for (i, curve) in curves.iter().enumerate() {
+        let color = COLOR[i % COLOR.len()];
+        chart
+            .draw_series(LineSeries::new(points(curve), color.stroke_width(2)))
+            .map_err(|e| e.to_string())?
+            .label(&curve.label)
+            .legend(move |(x, y)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2))
+            });
+    }
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::LowerLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(|e| e.to_string())
+}
+
+fn draw_on<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    curves: &[Curve],
+    title: &str,
+) -> Result<(), String> {
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+    let root = root
+        .titled(title, ("sans-serif", 24))
+        .map_err(|e| e.to_string())?;
+    if curves.iter().all(|c| c.time.is_none()) {
+        panel(&root, curves, "Iteration", |c| Some(&c.iteration))?;
+    } else {
+        let (left, right) = root.split_horizontally(root.dim_in_pixel().0 / 2);
+        panel(&left, curves, "Iteration", |c| Some(&c.iteration))?;
+        panel(&right, curves, "Time [s]", |c| c.time.as_ref())?;
+    }
+    root.present().map_err(|e| e.to_string())
+}
+
+pub fn draw(curves: &[Curve], title: &str, filepath: &str) -> Result<(), String> {
+    let size = (1200, 500);
+    if filepath.ends_with(".png") {
+        draw_on(
+            BitMapBackend::new(filepath, size).into_drawing_area(),
+            curves,
+            title,
+        )
+    } else if filepath.ends_with(".svg") {
+        draw_on(
+            SVGBackend::new(filepath, size).into_drawing_area(),
+            curves,
+            title,
+        )
+    } else {
+        Err(format!("unknown format of `{}` (.svg, .png)", filepath))
+    }
+}
@@ -1,15 +1,82 @@
 use crate::game::StrategyPolytope;
-use ndarray::Array1;
+use ndarray::{Array1, Zip};
 
 pub trait ProxFunction {
     fn grad(&self, x: Array1<f64>) -> Array1<f64>; // Return ∇d(x)
-    fn conj(&self, x: Array1<f64>) -> f64; // Return d(x)
+    fn value(&self, x: Array1<f64>) -> f64; // Return d(x)
+    fn conj(&self, x: Array1<f64>) -> f64; // Return d*(x)
     fn conj_grad(&self, x: Array1<f64>) -> Array1<f64>; // Return ∇d*(x)
     fn projection(&self, x: Array1<f64>, s: Array1<f64>) -> Array1<f64> {
         // Return ∇d*(∇d(z)-s)
         self.conj_grad(self.grad(x) - s)
     }
+    fn bregman(&self, x: Array1<f64>, z: Array1<f64>) -> f64 {
+        // Return d(x)-d(z)-<∇d(z),x-z>
+        let diff = &x - &z;
+        let inner = Zip::from(&self.grad(z.clone()))
+            .and(&diff)
+            .fold(0.0, |acc, g, d| if *d == 0.0 { acc } else { acc + g * d });
+        self.value(x) - self.value(z) - inner
+    }
     fn center(&self) -> &Array1<f64>;
+    fn max(&self) -> f64; // Return max_x d(x)
+    fn modulus(&self) -> f64; // Return the strong convexity modulus of d w.r.t. ||.||_1
+}
+
+// The weights w_i of the infosets in the dilated prox functions
+pub enum WeightScheme {
+    Uniform,    // w_i = 1
+    Kroer2020,  // w_i = 2 + 2 max_{j∈i} Σ_{i' under j} w_i' (Kroer et al. 2020)
+    Depth(f64), // w_i = factor^h_i, where h_i is the number of infosets on the longest path below i
+    Recursive,  // w_i = 1 + max_{j∈i} Σ_{i' under j} w_i'
+    Custom(Array1<f64>),
+}
+
+impl WeightScheme {
+    pub fn weights(&self, sp: &StrategyPolytope) -> Result<Array1<f64>, String> {
+        // Return w, indexed by the infosets of `sp`, or an error if it is not positive
+        let (base, scale): (f64, f64) = match self {
+            WeightScheme::Uniform => return Ok(Array1::ones(sp.par.len())),
+            WeightScheme::Custom(w) => {
+                if w.len() != sp.par.len() {
+                    return Err(format!("{} weights for {} infosets", w.len(), sp.par.len()));
+                }
+                if let Some(i) = w.iter().position(|&w| !(w > 0.0 && w.is_finite())) {
+                    return Err(format!(
+                        "the weight {} of infoset {} is not positive",
+                        w[i], i
+                    ));
+                }
+                return Ok(w.clone());
+            }
+            WeightScheme::Depth(factor) => {
+                if !(*factor > 0.0 && factor.is_finite()) {
+                    return Err(format!("the depth factor {} is not positive", factor));
+                }
+                let mut h: Array1<f64> = Array1::zeros(sp.par.len());
+                let mut hs: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+                for (i, &p) in sp.par.iter().enumerate().rev() {
+                    h[i] = (sp.idx[i]..sp.idx[i + 1])
+                        .map(|j| hs[j])
+                        .fold(0.0, f64::max);
+                    hs[p] = hs[p].max(h[i] + 1.0);
+                }
+                return Ok(h.mapv(|h| factor.powf(h)));
+            }
+            WeightScheme::Kroer2020 => (2.0, 2.0),
+            WeightScheme::Recursive => (1.0, 1.0),
+        };
+        let mut w: Array1<f64> = Array1::from_elem(sp.par.len(), base);
+        let mut ws: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        for (i, &p) in sp.par.iter().enumerate().rev() {
+            w[i] += scale
+                * (sp.idx[i]..sp.idx[i + 1])
+                    .map(|j| ws[j])
+                    .fold(f64::NEG_INFINITY, |m, v| v.max(m));
+            ws[p] += w[i]
+        }
+        Ok(w)
+    }
 }
 
 pub struct Normal<'a> {
@@ -22,22 +89,18 @@ pub struct Normal<'a> {
 
 impl<'a> Normal<'a> {
     pub fn new(sp: &'a StrategyPolytope) -> Self {
-        let mut w: Array1<f64> = Array1::ones(sp.par.len());
-        let mut ws: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
-        for (i, &p) in sp.par.iter().enumerate().rev() {
-            w[i] += (sp.idx[i]..sp.idx[i + 1])
-                .map(|j| ws[j])
-                .fold(f64::NEG_INFINITY, |m, v| v.max(m));
-            ws[p] += w[i]
-        }
-        let mut c: Array1<f64> = -ws;
-        for i in 0..sp.par.len() {
+        Self::with_weights(sp, WeightScheme::Recursive.weights(sp).unwrap())
+    }
+    pub fn with_weights(sp: &'a StrategyPolytope, w: Array1<f64>) -> Self {
+        let mut c: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        for (i, &p) in sp.par.iter().enumerate() {
+            c[p] -= w[i];
             for j in sp.idx[i]..sp.idx[i + 1] {
                 c[j] += w[i];
             }
         }
         let mut _center: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
-        let min: f64 = -conj(&sp, &mut _center, &w);
+        let min: f64 = -conj(sp, &mut _center, &w);
 
         Self {
             sp,
@@ -53,18 +116,29 @@ impl ProxFunction for Normal<'_> {
         // Return ∇d(x)
         (x.mapv(f64::ln) + 1.0) * &self.c
     }
-    fn conj(&self, mut x: Array1<f64>) -> f64 {
+    fn value(&self, x: Array1<f64>) -> f64 {
         // Return d(x)
-        conj(&self.sp, &mut x, &self.w) + self.min
+        x.mapv(xlnx).dot(&self.c) - self.min
+    }
+    fn conj(&self, mut x: Array1<f64>) -> f64 {
+        // Return d*(x)
+        conj(self.sp, &mut x, &self.w) + self.min
     }
     fn conj_grad(&self, mut x: Array1<f64>) -> Array1<f64> {
         // Return ∇d*(x)
-        conj(&self.sp, &mut x, &self.w);
+        conj(self.sp, &mut x, &self.w);
         x
     }
     fn center(&self) -> &Array1<f64> {
         &self._center
     }
+    fn max(&self) -> f64 {
+        // d is convex, so the maximum is attained at a vertex, where xlnx = 0.
+        -self.min
+    }
+    fn modulus(&self) -> f64 {
+        modulus(self.sp, &self.w)
+    }
 }
 
 pub struct Centering<'a> {
@@ -78,21 +152,25 @@ pub struct Centering<'a> {
 
 impl<'a> Centering<'a> {
     pub fn new(sp: &'a StrategyPolytope, _center: Array1<f64>) -> Self {
-        let mut w: Array1<f64> = Array1::ones(sp.par.len());
-        let mut ws: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
-        for (i, &p) in sp.par.iter().enumerate().rev() {
-            w[i] += (sp.idx[i]..sp.idx[i + 1])
-                .map(|j| ws[j])
-                .fold(f64::NEG_INFINITY, |m, v| v.max(m));
-            ws[p] += w[i]
-        }
-        let mut c0: Array1<f64> = -ws;
-        for i in 0..sp.par.len() {
+        Self::with_weights(sp, _center, WeightScheme::Recursive.weights(sp).unwrap())
+    }
+    pub fn with_weights(sp: &'a StrategyPolytope, _center: Array1<f64>, w: Array1<f64>) -> Self {
+        let mut c0: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        for (i, &p) in sp.par.iter().enumerate() {
+            c0[p] -= w[i];
             for j in sp.idx[i]..sp.idx[i + 1] {
                 c0[j] += w[i];
             }
         }
         let c1 = -(_center.mapv(f64::ln) + 1.0) * &c0;
+        for (i, _) in sp.par.iter().enumerate() {
+            if let Some(j) = (sp.idx[i]..sp.idx[i + 1]).find(|&j| !c1[j].is_finite()) {
+                panic!(
+                    "the center is not in the relative interior at infoset {}: x[{}] = {} (smooth it with a positive floor)",
+                    i, j, _center[j]
+                );
+            }
+        }
         let min = _center.mapv(|v| v * v.ln()).dot(&c0) + c1.dot(&_center);
 
         Self {
@@ -110,20 +188,31 @@ impl ProxFunction for Centering<'_> {
         // Return ∇d(x)
         (x.mapv(f64::ln) + 1.0) * &self.c0 + &self.c1
     }
-    fn conj(&self, mut x: Array1<f64>) -> f64 {
+    fn value(&self, x: Array1<f64>) -> f64 {
         // Return d(x)
+        x.mapv(xlnx).dot(&self.c0) + x.dot(&self.c1) - self.min
+    }
+    fn conj(&self, mut x: Array1<f64>) -> f64 {
+        // Return d*(x)
         x -= &self.c1;
-        conj(&self.sp, &mut x, &self.w) + self.min
+        conj(self.sp, &mut x, &self.w) + self.min
     }
     fn conj_grad(&self, mut x: Array1<f64>) -> Array1<f64> {
         // Return ∇d*(x)
         x -= &self.c1;
-        conj(&self.sp, &mut x, &self.w);
+        conj(self.sp, &mut x, &self.w);
         x
     }
     fn center(&self) -> &Array1<f64> {
         &self._center
     }
+    fn max(&self) -> f64 {
+        // d is convex, so the maximum is attained at a vertex, where xlnx = 0.
+        self.sp.maximize(self.c1.clone()) - self.min
+    }
+    fn modulus(&self) -> f64 {
+        modulus(self.sp, &self.w)
+    }
 }
 
 pub struct Farina2021<'a> {
@@ -136,16 +225,12 @@ pub struct Farina2021<'a> {
 
 impl<'a> Farina2021<'a> {
     pub fn new(sp: &'a StrategyPolytope) -> Self {
-        let mut w: Array1<f64> = Array1::ones(sp.par.len());
-        let mut ws: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
-        for (i, &p) in sp.par.iter().enumerate().rev() {
-            w[i] += (sp.idx[i]..sp.idx[i + 1])
-                .map(|j| ws[j])
-                .fold(f64::NEG_INFINITY, |m, v| v.max(m));
-            ws[p] += w[i]
-        }
-        let mut c0: Array1<f64> = -ws;
-        for i in 0..sp.par.len() {
+        Self::with_weights(sp, WeightScheme::Recursive.weights(sp).unwrap())
+    }
+    pub fn with_weights(sp: &'a StrategyPolytope, w: Array1<f64>) -> Self {
+        let mut c0: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        for (i, &p) in sp.par.iter().enumerate() {
+            c0[p] -= w[i];
             for j in sp.idx[i]..sp.idx[i + 1] {
                 c0[j] += w[i];
             }
@@ -155,7 +240,7 @@ impl<'a> Farina2021<'a> {
             c1[p] += w[i] * ((sp.idx[i + 1] - sp.idx[i]) as f64).ln();
         }
         let mut _center = -c1.clone();
-        assert!(conj(&sp, &mut _center, &w).abs() < 1e-9);
+        assert!(conj(sp, &mut _center, &w).abs() < 1e-9);
 
         Self {
             sp,
@@ -171,20 +256,279 @@ impl ProxFunction for Farina2021<'_> {
         // Return ∇d(x)
         (x.mapv(f64::ln) + 1.0) * &self.c0 + &self.c1
     }
-    fn conj(&self, mut x: Array1<f64>) -> f64 {
+    fn value(&self, x: Array1<f64>) -> f64 {
         // Return d(x)
+        x.mapv(xlnx).dot(&self.c0) + x.dot(&self.c1)
+    }
+    fn conj(&self, mut x: Array1<f64>) -> f64 {
+        // Return d*(x)
         x -= &self.c1;
-        conj(&self.sp, &mut x, &self.w)
+        conj(self.sp, &mut x, &self.w)
     }
     fn conj_grad(&self, mut x: Array1<f64>) -> Array1<f64> {
         // Return ∇d*(x)
         x -= &self.c1;
-        conj(&self.sp, &mut x, &self.w);
+        conj(self.sp, &mut x, &self.w);
+        x
+    }
+    fn center(&self) -> &Array1<f64> {
+        &self._center
+    }
+    fn max(&self) -> f64 {
+        // d is convex, so the maximum is attained at a vertex, where xlnx = 0.
+        self.sp.maximize(self.c1.clone())
+    }
+    fn modulus(&self) -> f64 {
+        modulus(self.sp, &self.w)
+    }
+}
+
+// The global entropy of Farina et al. 2022, i.e. the negative entropy of the
+// maximum entropy distribution over the vertices of the treeplex with mean x.
+// Picking the actions independently at the reached infosets attains the maximum,
+// so d is the dilated entropy with unit weights and d* is the log of the KOMWU kernel
+// Σ_v exp(<s,v>), both computed in time linear in the tree size.
+// Mirror descent with d is multiplicative weights over the vertices.
+pub struct GlobalEntropy<'a> {
+    sp: &'a StrategyPolytope,
+    w: Array1<f64>,
+    c: Array1<f64>, // the coefficient of `x\lnx`
+    _center: Array1<f64>,
+    log_vertices: f64,
+}
+
+impl<'a> GlobalEntropy<'a> {
+    pub fn new(sp: &'a StrategyPolytope) -> Self {
+        let w = WeightScheme::Uniform.weights(sp).unwrap();
+        let mut c: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        for (i, &p) in sp.par.iter().enumerate() {
+            c[p] -= w[i];
+            for j in sp.idx[i]..sp.idx[i + 1] {
+                c[j] += w[i];
+            }
+        }
+        let mut _center: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        let log_vertices: f64 = conj(sp, &mut _center, &w);
+
+        Self {
+            sp,
+            w,
+            c,
+            _center,
+            log_vertices,
+        }
+    }
+}
+impl ProxFunction for GlobalEntropy<'_> {
+    fn grad(&self, x: Array1<f64>) -> Array1<f64> {
+        // Return ∇d(x)
+        (x.mapv(f64::ln) + 1.0) * &self.c
+    }
+    fn value(&self, x: Array1<f64>) -> f64 {
+        // Return d(x)
+        x.mapv(xlnx).dot(&self.c) + self.log_vertices
+    }
+    fn conj(&self, mut x: Array1<f64>) -> f64 {
+        // Return d*(x)
+        conj(self.sp, &mut x, &self.w) - self.log_vertices
+    }
+    fn conj_grad(&self, mut x: Array1<f64>) -> Array1<f64> {
+        // Return ∇d*(x), the mean of the vertices under the weights exp(<x,v>)
+        conj(self.sp, &mut x, &self.w);
+        x
+    }
+    fn center(&self) -> &Array1<f64> {
+        &self._center
+    }
+    fn max(&self) -> f64 {
+        // The entropy is nonnegative, and zero at the vertices
+        self.log_vertices
+    }
+    fn modulus(&self) -> f64 {
+        // x_j <= x_p <= 1, so the Hessian of the dilated entropy dominates that of the
+        // dilated Euclidean distance
+        modulus(self.sp, &self.w).max(modulus_l2(self.sp, &self.w))
+    }
+}
+
+pub struct Euclidean<'a> {
+    sp: &'a StrategyPolytope,
+    _center: Array1<f64>,
+    min: f64,
+}
+
+impl<'a> Euclidean<'a> {
+    pub fn new(sp: &'a StrategyPolytope) -> Self {
+        let _center = project(sp, Array1::zeros(*sp.idx.last().unwrap()));
+        let min = 0.5 * _center.dot(&_center);
+        Self { sp, _center, min }
+    }
+}
+impl ProxFunction for Euclidean<'_> {
+    fn grad(&self, x: Array1<f64>) -> Array1<f64> {
+        // Return ∇d(x)
         x
     }
+    fn value(&self, x: Array1<f64>) -> f64 {
+        // Return d(x)
+        0.5 * x.dot(&x) - self.min
+    }
+    fn conj(&self, x: Array1<f64>) -> f64 {
+        // Return d*(x)
+        let z = project(self.sp, x.clone());
+        x.dot(&z) - self.value(z)
+    }
+    fn conj_grad(&self, x: Array1<f64>) -> Array1<f64> {
+        // Return ∇d*(x)
+        project(self.sp, x)
+    }
     fn center(&self) -> &Array1<f64> {
         &self._center
     }
+    fn max(&self) -> f64 {
+        // d is convex, so the maximum is attained at a vertex, where x^2 = x.
+        self.sp.maximize(Array1::from_elem(self._center.len(), 0.5)) - self.min
+    }
+    fn modulus(&self) -> f64 {
+        // ||h||_2^2 >= ||h||_1^2/(n-1) for h with h_0 = 0
+        1.0 / (self._center.len() - 1) as f64
+    }
+}
+
+pub struct DilatedEuclidean<'a> {
+    sp: &'a StrategyPolytope,
+    w: Array1<f64>,
+    _center: Array1<f64>,
+    min: f64,
+}
+
+impl<'a> DilatedEuclidean<'a> {
+    pub fn new(sp: &'a StrategyPolytope) -> Self {
+        Self::with_weights(sp, WeightScheme::Recursive.weights(sp).unwrap())
+    }
+    pub fn with_weights(sp: &'a StrategyPolytope, w: Array1<f64>) -> Self {
+        let mut _center: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+        let min: f64 = -conj_l2(sp, &mut _center, &w);
+
+        Self {
+            sp,
+            w,
+            _center,
+            min,
+        }
+    }
+}
+impl ProxFunction for DilatedEuclidean<'_> {
+    fn grad(&self, x: Array1<f64>) -> Array1<f64> {
+        // Return ∇d(x)
+        // d(x) = Σ_i w_i Σ_{j∈i} x_j^2/2x_p, so ∂d/∂x_j = w_i x_j/x_p - Σ_{i'} w_i' ||x_i'/x_j||^2/2
+        // for the infosets i' under j.
+        let mut g: Array1<f64> = Array1::zeros(x.len());
+        for (i, &p) in self.sp.par.iter().enumerate() {
+            if x[p] <= 0.0 {
+                continue;
+            }
+            let mut sq = 0.0;
+            for j in self.sp.idx[i]..self.sp.idx[i + 1] {
+                let z = x[j] / x[p];
+                g[j] += self.w[i] * z;
+                sq += z * z;
+            }
+            g[p] -= 0.5 * self.w[i] * sq;
+        }
+        g
+    }
+    fn value(&self, x: Array1<f64>) -> f64 {
+        // Return d(x)
+        let mut val = -self.min;
+        for (i, &p) in self.sp.par.iter().enumerate() {
+            if x[p] <= 0.0 {
+                continue;
+            }
+            let sq: f64 = (self.sp.idx[i]..self.sp.idx[i + 1])
+                .map(|j| x[j] * x[j])
+                .sum();
+            val += 0.5 * self.w[i] * sq / x[p];
+        }
+        val
+    }
+    fn conj(&self, mut x: Array1<f64>) -> f64 {
+        // Return d*(x)
+        conj_l2(self.sp, &mut x, &self.w) + self.min
+    }
+    fn conj_grad(&self, mut x: Array1<f64>) -> Array1<f64> {
+        // Return ∇d*(x)
+        conj_l2(self.sp, &mut x, &self.w);
+        x
+    }
+    fn center(&self) -> &Array1<f64> {
+        &self._center
+    }
+    fn max(&self) -> f64 {
+        // d is convex, so the maximum is attained at a vertex,
+        // where each reached infoset i contributes w_i/2.
+        let mut c: Array1<f64> = Array1::zeros(self._center.len());
+        for (i, &p) in self.sp.par.iter().enumerate() {
+            c[p] += 0.5 * self.w[i];
+        }
+        self.sp.maximize(c) - self.min
+    }
+    fn modulus(&self) -> f64 {
+        modulus_l2(self.sp, &self.w)
+    }
+}
+
+fn modulus(sp: &StrategyPolytope, w: &Array1<f64>) -> f64 {
+    // Return a lower bound of the strong convexity modulus of the dilated entropy
+    // Σ_i w_i Σ_{j∈i} x_j ln(x_j/x_p) w.r.t. ||.||_1.
+    //
+    // Let T_j be the l1 norm of h below the sequence j. By induction with
+    // Cauchy-Schwarz, the Hessian of the subtree of j is at least
+    // α_j T_j^2/x_j - β_j h_j^2/x_j, where β_j is the total weight of
+    // the infosets under j and 1/α_j = Σ_i max_{k∈i} 1/(w_i-β_k) + 1/α_k.
+    let mut inv_alpha: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+    let mut beta: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+    for (i, &p) in sp.par.iter().enumerate().rev() {
+        inv_alpha[p] += (sp.idx[i]..sp.idx[i + 1])
+            .map(|j| {
+                if w[i] > beta[j] {
+                    1.0 / (w[i] - beta[j]) + inv_alpha[j]
+                } else {
+                    f64::INFINITY
+                }
+            })
+            .fold(f64::NEG_INFINITY, |m, v| v.max(m));
+        beta[p] += w[i];
+    }
+    1.0 / inv_alpha[0]
+}
+
+fn modulus_l2(sp: &StrategyPolytope, w: &Array1<f64>) -> f64 {
+    // Return a lower bound of the strong convexity modulus of Σ_i w_i x_p ψ(x_i/x_p) w.r.t. ||.||_1
+    // if the Hessian of ψ is at least the identity on the simplex.
+    //
+    // With u_j = h_j - (x_j/x_p)h_p, the Hessian is at least Σ_i w_i Σ_{j∈i} u_j^2/x_p >= Σ w u^2,
+    // and ||h||_1 <= Σ_j g_j|u_j| where g_j = 1 + Σ_{i under j} max_{k∈i} g_k.
+    // By Cauchy-Schwarz, the modulus is at least 1/Σ_j g_j^2/w_i.
+    let mut g: Array1<f64> = Array1::ones(*sp.idx.last().unwrap());
+    let mut inv = 0.0;
+    for (i, &p) in sp.par.iter().enumerate().rev() {
+        g[p] += (sp.idx[i]..sp.idx[i + 1])
+            .map(|j| {
+                inv += g[j] * g[j] / w[i];
+                g[j]
+            })
+            .fold(f64::NEG_INFINITY, |m, v| v.max(m));
+    }
+    1.0 / inv
+}
+
+fn xlnx(v: f64) -> f64 {
+    if v > 0.0 {
+        v * v.ln()
+    } else {
+        0.0
+    }
 }
 
 fn conj(sp: &StrategyPolytope, x: &mut Array1<f64>, w: &Array1<f64>) -> f64 {
@@ -227,3 +571,419 @@ fn conj(sp: &StrategyPolytope, x: &mut Array1<f64>, w: &Array1<f64>) -> f64 {
     }
     val
 }
+
+fn conj_l2(sp: &StrategyPolytope, x: &mut Array1<f64>, w: &Array1<f64>) -> f64 {
+    // x ← ∇d*(x) for the dilated Euclidean distance
+    // Return d*(x)
+    for (i, &p) in sp.par.iter().enumerate().rev() {
+        let l: usize = sp.idx[i];
+        let r: usize = sp.idx[i + 1];
+        let g: Vec<f64> = (l..r).map(|j| x[j]).collect();
+        let mut z: Vec<f64> = g.iter().map(|v| v / w[i]).collect();
+        simplex_projection(&mut z);
+        // max_{z∈Δ} <g,z> - w_i||z||^2/2
+        let val: f64 = g
+            .iter()
+            .zip(&z)
+            .map(|(g, z)| g * z - 0.5 * w[i] * z * z)
+            .sum();
+        for (j, z) in (l..r).zip(z) {
+            x[j] = z;
+        }
+        x[p] += val;
+    }
+    let val: f64 = x[0];
+    x[0] = 1.0;
+    for (i, &p) in sp.par.iter().enumerate() {
+        let xp = x[p];
+        for j in sp.idx[i]..sp.idx[i + 1] {
+            x[j] *= xp;
+        }
+    }
+    val
+}
+
+fn simplex_projection(v: &mut [f64]) {
+    // v ← argmin_{z ∈ Δ} ||z-v||^2 by sorting (Duchi et al. 2008)
+    let mut u = v.to_vec();
+    u.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    let mut sum = 0.0;
+    let mut theta = 0.0;
+    for (k, &uk) in u.iter().enumerate() {
+        sum += uk;
+        let t = (sum - 1.0) / (k + 1) as f64;
+        if uk - t > 0.0 {
+            theta = t;
+        } else {
+            break;
+        }
+    }
+    for vk in v.iter_mut() {
+        *vk = (*vk - theta).max(0.0);
+    }
+}
+
+// A piecewise linear function through `(xs[k], ys[k])`,
+// extended by the slopes `left` and `right` outside of the breakpoints.
+#[derive(Clone)]
+struct PiecewiseLinear {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    left: f64,
+    right: f64,
+}
+impl PiecewiseLinear {
+    fn eval(&self, x: f64) -> f64 {
+        let n = self.xs.len();
+        if x <= self.xs[0] {
+            return self.ys[0] + self.left * (x - self.xs[0]);
+        }
+        if x >= self.xs[n - 1] {
+            return self.ys[n - 1] + self.right * (x - self.xs[n - 1]);
+        }
+        let k = self.xs.partition_point(|&v| v <= x);
+        let (x0, x1) = (self.xs[k - 1], self.xs[k]);
+        let (y0, y1) = (self.ys[k - 1], self.ys[k]);
+        y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+    }
+    fn sum(fs: &[PiecewiseLinear]) -> Self {
+        let mut xs: Vec<f64> = fs.iter().flat_map(|f| f.xs.iter().copied()).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        xs.dedup();
+        let ys = xs
+            .iter()
+            .map(|&x| fs.iter().map(|f| f.eval(x)).sum())
+            .collect();
+        Self {
+            xs,
+            ys,
+            left: fs.iter().map(|f| f.left).sum(),
+            right: fs.iter().map(|f| f.right).sum(),
+        }
+    }
+    fn inverse(&self) -> Self {
+        // Assume that `self` is strictly increasing on [xs[0], ∞)
+        Self {
+            xs: self.ys.clone(),
+            ys: self.xs.clone(),
+            left: 0.0,
+            right: 1.0 / self.right,
+        }
+    }
+}
+
+fn project(sp: &StrategyPolytope, z: Array1<f64>) -> Array1<f64> {
+    // Return argmin_{x ∈ sp} ||x-z||^2
+    //
+    // For a sequence j, f[j](t) is the derivative of the minimum of the
+    // subtree of j under x_j = t, and for an infoset i, mu[i](t) is the
+    // derivative of the minimum of the subtree of i when i receives the mass t.
+    // Both are increasing and piecewise linear.
+    let n = *sp.idx.last().unwrap();
+    let mut children: Vec<Vec<PiecewiseLinear>> = (0..n).map(|_| vec![]).collect();
+    let mut mu: Vec<PiecewiseLinear> = Vec::with_capacity(sp.par.len());
+    let mut inv: Vec<Vec<PiecewiseLinear>> = Vec::with_capacity(sp.par.len());
+    for (i, &p) in sp.par.iter().enumerate().rev() {
+        // inv_i[k](λ) is the optimal x_j for the multiplier λ, where f[j] = t-z_j+Σmu
+        let inv_i: Vec<PiecewiseLinear> = (sp.idx[i]..sp.idx[i + 1])
+            .map(|j| {
+                let mut fs = std::mem::take(&mut children[j]);
+                fs.push(PiecewiseLinear {
+                    xs: vec![0.0],
+                    ys: vec![-z[j]],
+                    left: 1.0,
+                    right: 1.0,
+                });
+                PiecewiseLinear::sum(&fs).inverse()
+            })
+            .collect();
+        let mu_i = PiecewiseLinear::sum(&inv_i).inverse();
+        children[p].push(mu_i.clone());
+        mu.push(mu_i);
+        inv.push(inv_i);
+    }
+    mu.reverse();
+    inv.reverse();
+
+    let mut x = z;
+    x[0] = 1.0;
+    for (i, &p) in sp.par.iter().enumerate() {
+        let lambda = mu[i].eval(x[p]);
+        for (k, j) in (sp.idx[i]..sp.idx[i + 1]).enumerate() {
+            x[j] = inv[i][k].eval(lambda).max(0.0);
+        }
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simplex_brute_force(v: &[f64]) -> Vec<f64> {
+        // The closest point of the simplex over the projections onto the faces
+        let n = v.len();
+        let mut best = (f64::INFINITY, vec![]);
+        for support in 1..1u32 << n {
+            let k = support.count_ones() as f64;
+            let shift = ((0..n).filter(|&i| support >> i & 1 == 1))
+                .map(|i| v[i])
+                .sum::<f64>()
+                - 1.0;
+            let z: Vec<f64> = (0..n)
+                .map(|i| match support >> i & 1 {
+                    1 => v[i] - shift / k,
+                    _ => 0.0,
+                })
+                .collect();
+            let dist: f64 = z.iter().zip(v).map(|(a, b)| (a - b).powi(2)).sum();
+            if z.iter().all(|&a| a >= -1e-12) && dist < best.0 {
+                best = (dist, z);
+            }
+        }
+        best.1
+    }
+
+    #[test]
+    fn simplex_projection_matches_brute_force() {
+        for v in [
+            vec![0.2, 0.3, 0.5],
+            vec![1.0, 1.0, 1.0],
+            vec![-1.0, 0.5, 2.0],
+            vec![3.0, -2.0, 0.1, 0.4],
+            vec![0.0, 0.0, 0.0, 0.0],
+            vec![-5.0, -4.0],
+        ] {
+            let mut z = v.clone();
+            simplex_projection(&mut z);
+            assert!(z.iter().all(|&a| a >= 0.0));
+            assert!((z.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            for (a, b) in z.iter().zip(simplex_brute_force(&v)) {
+                assert!((a - b).abs() < 1e-12, "{:?} -> {:?}", v, z);
+            }
+        }
+    }
+
+    fn treeplex() -> StrategyPolytope {
+        // An infoset with the actions 1, 2, and an infoset under the action 1
+        // with the actions 3, 4
+        StrategyPolytope {
+            par: vec![0, 1],
+            idx: vec![1, 3, 5],
+            obs: vec![],
+            action: vec![],
+        }
+    }
+
+    #[test]
+    fn treeplex_projection_stays_feasible() {
+        let sp = treeplex();
+        for z in [
+            [0.0, 0.0, 0.0, 0.0, 0.0],
+            [1.0, 2.0, -1.0, 0.5, 0.5],
+            [1.0, -3.0, 0.2, 4.0, 4.0],
+            [0.0, 0.3, 0.3, 1.0, -1.0],
+        ] {
+            let x = project(&sp, Array1::from(z.to_vec()));
+            assert!(x.iter().all(|&a| a >= 0.0), "{}", x);
+            assert!((x[0] - 1.0).abs() < 1e-12);
+            assert!((x[1] + x[2] - 1.0).abs() < 1e-12, "{}", x);
+            assert!((x[3] + x[4] - x[1]).abs() < 1e-12, "{}", x);
+        }
+    }
+
+    #[test]
+    fn treeplex_projection_matches_brute_force() {
+        // The treeplex is {(1, a, 1-a, b, a-b) : 0 <= b <= a <= 1}, searched on a grid
+        let sp = treeplex();
+        let n = 400;
+        for z in [
+            [1.0, 0.7, 0.1, 0.2, 0.3],
+            [1.0, 2.0, -1.0, 0.5, 0.5],
+            [1.0, -0.2, 0.4, 0.9, -0.3],
+            [1.0, 0.5, 0.5, 0.25, 0.25],
+        ] {
+            let x = project(&sp, Array1::from(z.to_vec()));
+            let dist = |x: &[f64]| -> f64 { (1..5).map(|j| (x[j] - z[j]).powi(2)).sum() };
+            let mut best = f64::INFINITY;
+            for i in 0..=n {
+                for k in 0..=i {
+                    let (a, b) = (i as f64 / n as f64, k as f64 / n as f64);
+                    best = best.min(dist(&[1.0, a, 1.0 - a, b, a - b]));
+                }
+            }
+            let d = dist(x.as_slice().unwrap());
+            assert!(
+                d <= best + 1e-12,
+                "{:?} -> {} is farther than the grid",
+                z,
+                x
+            );
+            assert!(d >= best - 1e-4, "{:?} -> {}", z, x);
+        }
+    }
+
+    #[test]
+    fn invalid_weights() {
+        let sp = treeplex();
+        assert!(WeightScheme::Custom(Array1::from(vec![1.0, 2.0]))
+            .weights(&sp)
+            .is_ok());
+        for w in [
+            vec![1.0],
+            vec![1.0, 0.0],
+            vec![-1.0, 1.0],
+            vec![f64::NAN, 1.0],
+        ] {
+            assert!(WeightScheme::Custom(Array1::from(w)).weights(&sp).is_err());
+        }
+        assert!(WeightScheme::Depth(2.0).weights(&sp).is_ok());
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(WeightScheme::Depth(factor).weights(&sp).is_err());
+        }
+    }
+
+    fn vertices(sp: &StrategyPolytope) -> Vec<Array1<f64>> {
+        // The pure strategies, by choosing an action at every infoset
+        let mut choice = vec![0; sp.par.len()];
+        let mut vertices: Vec<Array1<f64>> = vec![];
+        loop {
+            let mut x: Array1<f64> = Array1::zeros(*sp.idx.last().unwrap());
+            x[0] = 1.0;
+            for (i, &p) in sp.par.iter().enumerate() {
+                x[sp.idx[i] + choice[i]] = x[p];
+            }
+            if !vertices.contains(&x) {
+                vertices.push(x);
+            }
+            // The next choices like an odometer
+            let Some(i) = (0..choice.len()).find(|&i| choice[i] + 1 < sp.idx[i + 1] - sp.idx[i])
+            else {
+                return vertices;
+            };
+            choice[i] += 1;
+            choice[..i].fill(0);
+        }
+    }
+
+    fn samples(sp: &StrategyPolytope, n: usize) -> Vec<Array1<f64>> {
+        // Points in the relative interior, as the mixtures of all the vertices
+        // with the weights of a linear congruential generator
+        let vertices = vertices(sp);
+        let mut seed: u64 = 1;
+        let mut uniform = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..n)
+            .map(|k| {
+                // Sharpen the weights so that some points are near the boundary
+                let w: Vec<f64> = vertices
+                    .iter()
+                    .map(|_| uniform().powi(1 + 4 * (k % 4) as i32))
+                    .collect();
+                let total: f64 = w.iter().sum();
+                (vertices.iter().zip(&w)).fold(Array1::zeros(vertices[0].len()), |x, (v, w)| {
+                    x + v * (w / total)
+                })
+            })
+            .collect()
+    }
+
+    fn kuhn() -> crate::game::Game {
+        crate::game::Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap()
+    }
+
+    fn prox_functions(sp: &StrategyPolytope) -> Vec<(&'static str, Box<dyn ProxFunction + '_>)> {
+        let center = samples(sp, 1).remove(0);
+        vec![
+            ("normal", Box::new(Normal::new(sp))),
+            ("farina", Box::new(Farina2021::new(sp))),
+            ("global", Box::new(GlobalEntropy::new(sp))),
+            ("euclidean", Box::new(Euclidean::new(sp))),
+            ("dilated-euclidean", Box::new(DilatedEuclidean::new(sp))),
+            ("centering", Box::new(Centering::new(sp, center))),
+        ]
+    }
+
+    #[test]
+    fn global_entropy_is_log_sum_exp_over_vertices() {
+        // d*(s) = ln Σ_v exp<s,v> - ln|V| and ∇d*(s) is the mean of the vertices
+        // under the weights exp<s,v>
+        let game = kuhn();
+        for (sp, n) in [(&game.sp1, 27), (&game.sp2, 64)] {
+            let vertices = vertices(sp);
+            assert_eq!(vertices.len(), n);
+            let pf = GlobalEntropy::new(sp);
+            assert!((pf.max() - (n as f64).ln()).abs() < 1e-12);
+            for (k, x) in samples(sp, 8).into_iter().enumerate() {
+                let s = (x - 0.3) * (k as f64 - 3.0);
+                let weights: Vec<f64> = vertices.iter().map(|v| s.dot(v).exp()).collect();
+                let total: f64 = weights.iter().sum();
+                let conj = total.ln() - (n as f64).ln();
+                assert!(
+                    (pf.conj(s.clone()) - conj).abs() < 1e-10,
+                    "{} vs {}",
+                    pf.conj(s),
+                    conj
+                );
+                let mean = (vertices.iter().zip(&weights))
+                    .fold(Array1::<f64>::zeros(s.len()), |m, (v, w)| {
+                        m + v * (w / total)
+                    });
+                let grad = pf.conj_grad(s);
+                assert!(
+                    (&grad - &mean).iter().all(|d| d.abs() < 1e-10),
+                    "{} vs {}",
+                    grad,
+                    mean
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn max_is_attained_at_a_vertex() {
+        let game = kuhn();
+        for sp in [&game.sp1, &game.sp2] {
+            let vertices = vertices(sp);
+            for (name, pf) in prox_functions(sp) {
+                let max =
+                    (vertices.iter()).fold(f64::NEG_INFINITY, |m, v| pf.value(v.clone()).max(m));
+                assert!(
+                    (pf.max() - max).abs() < 1e-9 * max.max(1.0),
+                    "{}: {} vs {}",
+                    name,
+                    pf.max(),
+                    max
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn modulus_is_at_most_the_strong_convexity() {
+        // D(x,z) >= σ/2 ||x-z||_1^2 for the modulus σ
+        let game = kuhn();
+        for sp in [&game.sp1, &game.sp2] {
+            let points = samples(sp, 40);
+            for (name, pf) in prox_functions(sp) {
+                let modulus = pf.modulus();
+                assert!(modulus > 0.0, "{}", name);
+                for (x, z) in points.iter().zip(points.iter().skip(1)) {
+                    let norm = (x - z).mapv(f64::abs).sum();
+                    let bregman = pf.bregman(x.clone(), z.clone());
+                    assert!(
+                        bregman >= 0.5 * modulus * norm * norm * (1.0 - 1e-9),
+                        "{}: D = {:e} < σ/2 ||x-z||^2 = {:e}",
+                        name,
+                        bregman,
+                        0.5 * modulus * norm * norm
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::game::{Game, SparseMatrix, StrategyPolytope};
+use crate::logging;
+use crate::metrics;
+use crate::result::{SolveResult, State};
+use crate::stop;
+use log::{debug, info, warn};
+use ndarray::{concatenate, s, Array1, Array2, Axis};
+use std::time::Instant;
+
+// The agent quantal response equilibrium (McKelvey and Palfrey 1998) with the rationality λ:
+// at every infoset, the player plays the logit response softmax(λq) to the expected values q
+// of its actions conditional on reaching the infoset, whose beliefs come from the chance
+// probabilities `C` of the game file and the strategy of the opponent.
+// λ = 0 gives the uniform behavior, and λ → ∞ approaches a Nash equilibrium.
+// The QRE is traced by the homotopy from the uniform behavior at λ = 0, where each λ is
+// reached by Newton's method on the log-probabilities warm-started from the previous one,
+// halving the increment of λ whenever Newton's method fails. The Jacobian is dense,
+// which suits the games with up to a few thousand sequences.
+pub struct QRE<'a> {
+    game: &'a Game,
+    chance: &'a (SparseMatrix, SparseMatrix),
+    tol: f64,
+}
+
+// The number m*exp(e), whose exponent does not underflow like the reach probabilities
+// of the sequences played with the probabilities about exp(-λq) at large λ
+#[derive(Clone, Copy)]
+struct Scaled {
+    m: f64,
+    e: f64,
+}
+impl Scaled {
+    const ZERO: Self = Self { m: 0.0, e: 0.0 };
+    fn add(self, other: Self) -> Self {
+        if self.m == 0.0 {
+            return other;
+        }
+        if other.m == 0.0 {
+            return self;
+        }
+        let e = self.e.max(other.e);
+        Self {
+            m: self.m * (self.e - e).exp() + other.m * (other.e - e).exp(),
+            e,
+        }
+    }
+    fn scale(self, log: f64) -> Self {
+        Self {
+            m: self.m,
+            e: self.e + log,
+        }
+    }
+    fn ratio(self, other: Self) -> f64 {
+        self.m / other.m * (self.e - other.e).exp()
+    }
+}
+
+fn log_sequence(sp: &StrategyPolytope, log_b: &Array1<f64>) -> Array1<f64> {
+    // Return the log-probabilities of the sequences played by the behavioral strategy `log_b`
+    let mut log_x = log_b.clone();
+    log_x[0] = 0.0;
+    for (i, &p) in sp.par.iter().enumerate() {
+        for j in sp.idx[i]..sp.idx[i + 1] {
+            log_x[j] += log_x[p];
+        }
+    }
+    log_x
+}
+
+fn dot(mat: &SparseMatrix, n_row: usize, log_x: &Array1<f64>) -> Vec<Scaled> {
+    // Return mat*exp(log_x), keeping the signs in the mantissas
+    let mut prod = vec![Scaled::ZERO; n_row];
+    for (r, c, d) in mat.entries() {
+        prod[r] = prod[r].add(Scaled { m: d, e: log_x[c] });
+    }
+    prod
+}
+
+fn normalize(sp: &StrategyPolytope, log_b: &mut Array1<f64>) {
+    // Shift the log-probabilities of each infoset to sum up to 1
+    for i in 0..sp.par.len() {
+        let (l, r) = (sp.idx[i], sp.idx[i + 1]);
+        let max = (l..r).fold(f64::NEG_INFINITY, |m, j| log_b[j].max(m));
+        let log_z = max + (l..r).map(|j| (log_b[j] - max).exp()).sum::<f64>().ln();
+        for j in l..r {
+            log_b[j] -= log_z;
+        }
+    }
+}
+
+fn respond(
+    sp: &StrategyPolytope,
+    log_b: &Array1<f64>,
+    mut value: Vec<Scaled>,
+    mut reach: Vec<Scaled>,
+    lambda: f64,
+) -> Array1<f64> {
+    // Return the log-probabilities of the logit response to the behavioral strategy `log_b`
+    // of the player itself, where `value` and `reach` are the expected utility and the
+    // probability of chance and the opponent reaching the terminal histories right after
+    // each sequence. The utility of an action conditional on its infoset is its value over
+    // the reach of the infoset, and the response is uniform if the infoset is never reached.
+    let mut response = Array1::zeros(log_b.len());
+    for (i, &p) in sp.par.iter().enumerate().rev() {
+        let (l, r) = (sp.idx[i], sp.idx[i + 1]);
+        let total = (l..r).fold(Scaled::ZERO, |t, j| t.add(reach[j].scale(log_b[j])));
+        for j in l..r {
+            response[j] = if total.m > 0.0 {
+                lambda * value[j].ratio(total)
+            } else {
+                0.0
+            };
+        }
+        value[p] = (l..r).fold(value[p], |v, j| v.add(value[j].scale(log_b[j])));
+        reach[p] = reach[p].add(total);
+    }
+    normalize(sp, &mut response);
+    response
+}
+
+fn solve_linear(mut tab: Array2<f64>) -> Option<Array1<f64>> {
+    // Solve the system whose augmented matrix is `tab` by the Gaussian elimination
+    // with partial pivoting, or return None if it is singular
+    let n = tab.nrows();
+    for k in 0..n {
+        let l = (k..n)
+            .max_by(|&a, &b| tab[[a, k]].abs().total_cmp(&tab[[b, k]].abs()))
+            .unwrap();
+        if tab[[l, k]].abs() < 1e-300 {
+            return None;
+        }
+        if l != k {
+            for j in k..=n {
+                tab.swap([k, j], [l, j]);
+            }
+        }
+        let row = tab.row(k).to_owned();
+        for i in k + 1..n {
+            let f = tab[[i, k]] / row[k];
+            if f != 0.0 {
+                tab.row_mut(i).scaled_add(-f, &row);
+            }
+        }
+    }
+    let mut x = Array1::zeros(n);
+    for k in (0..n).rev() {
+        let s = tab[[k, n]] - (k + 1..n).map(|j| tab[[k, j]] * x[j]).sum::<f64>();
+        x[k] = s / tab[[k, k]];
+    }
+    Some(x)
+}
+
+impl<'a> QRE<'a> {
+    pub fn new(game: &'a Game, tol: f64) -> Result<Self, String> {
+        let chance = game.chance.as_ref().ok_or(
+            "the game has no chance probabilities `C` for the beliefs; rebuild it by py/script.py",
+        )?;
+        Ok(Self { game, chance, tol })
+    }
+    fn split(&self, u: &Array1<f64>) -> (Array1<f64>, Array1<f64>) {
+        // Split the log-probabilities of both players, normalized at each infoset
+        let n1 = *self.game.sp1.idx.last().unwrap();
+        let mut log_b1 = u.slice(s![..n1]).to_owned();
+        let mut log_b2 = u.slice(s![n1..]).to_owned();
+        normalize(&self.game.sp1, &mut log_b1);
+        normalize(&self.game.sp2, &mut log_b2);
+        (log_b1, log_b2)
+    }
+    fn uniform(&self) -> Array1<f64> {
+        // The log-probabilities of the uniform behavior, i.e., the QRE with λ = 0
+        let n = self.game.sp1.idx.last().unwrap() + self.game.sp2.idx.last().unwrap();
+        let (log_b1, log_b2) = self.split(&Array1::zeros(n));
+        concatenate![Axis(0), log_b1, log_b2]
+    }
+    fn respond(&self, u: &Array1<f64>, lambda: f64) -> Array1<f64> {
+        // Return the log-probabilities of the logit responses of both players to `u`
+        let (c, c_t) = self.chance;
+        let (sp1, sp2) = (&self.game.sp1, &self.game.sp2);
+        let (n1, n2) = (*sp1.idx.last().unwrap(), *sp2.idx.last().unwrap());
+        let (log_b1, log_b2) = self.split(u);
+        let log_x = log_sequence(sp1, &log_b1);
+        let log_y = log_sequence(sp2, &log_b2);
+        // Player 1 minimizes x^TAy and player 2 maximizes it
+        let value1 = dot(&self.game.mat_a, n1, &log_y)
+            .into_iter()
+            .map(|v| Scaled { m: -v.m, e: v.e })
+            .collect();
+        let value2 = dot(&self.game.mat_a_t, n2, &log_x);
+        let r1 = respond(sp1, &log_b1, value1, dot(c, n1, &log_y), lambda);
+        let r2 = respond(sp2, &log_b2, value2, dot(c_t, n2, &log_x), lambda);
+        concatenate![Axis(0), r1, r2]
+    }
+    fn residual(&self, u: &Array1<f64>, lambda: f64) -> (Array1<f64>, f64) {
+        // Return u minus its logit response and the largest difference of their probabilities,
+        // where the former is not normalized to keep the Jacobian nonsingular
+        let r = self.respond(u, lambda);
+        let (log_b1, log_b2) = self.split(u);
+        let log_b = concatenate![Axis(0), log_b1, log_b2];
+        let diff =
+            (log_b.iter().zip(&r)).fold(0.0, |m: f64, (a, b)| (a.exp() - b.exp()).abs().max(m));
+        (u - r, diff)
+    }
+    fn newton(&self, lambda: f64, mut u: Array1<f64>, step: usize) -> (Option<Array1<f64>>, usize) {
+        // Return the QRE found by Newton's method from `u` and the number of iterations,
+        // or None if the iterates stop decreasing the residual within `step` iterations
+        let n = u.len();
+        let h = 1e-7;
+        for k in 0..step {
+            let (f, diff) = self.residual(&u, lambda);
+            if diff < self.tol {
+                return (Some(u), k);
+            }
+            // The Jacobian of the residual by the forward differences
+            let mut tab = Array2::zeros((n, n + 1));
+            for j in 0..n {
+                let mut v = u.clone();
+                v[j] += h;
+                let (fj, _) = self.residual(&v, lambda);
+                tab.column_mut(j).assign(&((fj - &f) / h));
+            }
+            tab.column_mut(n).assign(&f);
+            let Some(delta) = solve_linear(tab) else {
+                return (None, k);
+            };
+            // Backtrack until the residual decreases
+            let norm = f.dot(&f).sqrt();
+            let mut t = 1.0;
+            loop {
+                let v = &u - &(t * &delta);
+                let (fv, _) = self.residual(&v, lambda);
+                if fv.dot(&fv).sqrt() < (1.0 - 1e-4 * t) * norm {
+                    u = v;
+                    break;
+                }
+                t *= 0.5;
+                if t < 1e-3 {
+                    return (None, k);
+                }
+            }
+        }
+        (None, step)
+    }
+    fn continue_to(
+        &self,
+        from: (f64, Array1<f64>),
+        lambda: f64,
+        step: usize,
+    ) -> ((f64, Array1<f64>), usize) {
+        // Follow the QRE from `from` up to `lambda` within `step` iterations of Newton's method,
+        // and return the last QRE reached and the number of iterations
+        let (mut at, mut u) = from;
+        let mut increment = lambda - at;
+        let mut k = 0;
+        while at < lambda && k < step && !stop::requested() {
+            let next = (at + increment).min(lambda);
+            let (found, iterations) = self.newton(next, u.clone(), step - k);
+            k += iterations.max(1);
+            match found {
+                Some(v) => {
+                    (at, u) = (next, v);
+                    increment *= 2.0;
+                }
+                None if increment > 1e-12 * lambda => increment *= 0.5,
+                None => break,
+            }
+        }
+        ((at, u), k)
+    }
+    fn sequences(&self, u: &Array1<f64>) -> (Array1<f64>, Array1<f64>) {
+        let (log_b1, log_b2) = self.split(u);
+        (
+            log_sequence(&self.game.sp1, &log_b1).mapv(f64::exp),
+            log_sequence(&self.game.sp2, &log_b2).mapv(f64::exp),
+        )
+    }
+    pub fn solve(&self, lambda: f64, step: usize) -> SolveResult {
+        // Follow the homotopy from λ = 0 within `step` iterations of Newton's method
+        let start = Instant::now();
+        let ((at, u), k) = self.continue_to((0.0, self.uniform()), lambda, step);
+        if at < lambda {
+            warn!("reached lambda = {:e} only, short of {:e}", at, lambda);
+        }
+        debug!("{} iterations of Newton's method", k);
+        let (x, y) = self.sequences(&u);
+        // Only the final iterate is evaluated, numbered after the iterations run
+        let error = vec![(k, metrics::observe(k, self.game, &x, &y, &[]))];
+        info!("final error = {:e}", error[0].1);
+        SolveResult::new(x, y, error, start).state(State::Qre { steps: k })
+    }
+    pub fn trace(&self, lambdas: &[f64], step: usize) -> Vec<(f64, Array1<f64>, Array1<f64>)> {
+        // Return the QRE at each λ of the increasing `lambdas` reached within `step`
+        // iterations of Newton's method from the previous one
+        let mut from = (0.0, self.uniform());
+        let mut path = vec![];
+        for &lambda in logging::progress(lambdas.iter()) {
+            let k;
+            (from, k) = self.continue_to(from, lambda, step);
+            debug!(
+                "{} iterations of Newton's method to lambda = {:e}",
+                k, lambda
+            );
+            if from.0 < lambda {
+                warn!("reached lambda = {:e} only, short of {:e}", from.0, lambda);
+                break;
+            }
+            let (x, y) = self.sequences(&from.1);
+            path.push((lambda, x, y));
+            if stop::requested() {
+                break;
+            }
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kuhn() -> Game {
+        Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap()
+    }
+
+    #[test]
+    fn uniform_at_zero() {
+        let game = kuhn();
+        let result = QRE::new(&game, 1e-10).unwrap().solve(0.0, 100);
+        assert!((&result.x - &game.sp1.uniform())
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+        assert!((&result.y - &game.sp2.uniform())
+            .iter()
+            .all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn logit_response_facing_bet() {
+        // In Kuhn poker, folding to a bet loses the ante and calling it wins or loses 2
+        // against the cards the opponent plays into the bet, so the logit response at the
+        // infosets facing a bet is the sigmoid of λ times their difference
+        let game = kuhn();
+        let lambda = 3.0;
+        let result = QRE::new(&game, 1e-12).unwrap().solve(lambda, 100);
+        let (b1, b2) = (game.sp1.to_labels(&result.x), game.sp2.to_labels(&result.y));
+        let cards = ["J", "Q", "K"];
+        for (c, card) in cards.iter().enumerate() {
+            // The beliefs of the player facing the bet are the probabilities of the bet
+            let bet1 = cards.map(|other| b1[other]["Bet"]);
+            let bet2 = cards.map(|other| b2[&format!("{},Check", other)]["Bet"]);
+            let facing = [
+                (b2[&format!("{},Bet", card)]["Call"], bet1),
+                (b1[&format!("{},Check,Bet", card)]["Call"], bet2),
+            ];
+            for (call, bet) in facing {
+                let (mut value, mut reach) = (0.0, 0.0);
+                for o in (0..3).filter(|&o| o != c) {
+                    let p = bet[o];
+                    value += p * if c > o { 2.0 } else { -2.0 };
+                    reach += p;
+                }
+                let q = value / reach + 1.0;
+                let expected = 1.0 / (1.0 + (-lambda * q).exp());
+                assert!((call - expected).abs() < 1e-9, "{} vs {}", call, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn nash_at_large_lambda() {
+        let game = kuhn();
+        let result = QRE::new(&game, 1e-10).unwrap().solve(1000.0, 1000);
+        assert!(
+            result.final_error() < 1e-3,
+            "error = {:e}",
+            result.final_error()
+        );
+        let value = result.x.dot(&game.mat_a.dot(&result.y));
+        assert!((value - 1.0 / 18.0).abs() < 1e-3, "value = {}", value);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::double_oracle::Support;
+use ndarray::Array1;
+use std::time::{Duration, Instant};
+
+// The output of a solver
+pub struct SolveResult {
+    pub x: Array1<f64>, // the strategies of the solver, weighted over the iterates by `weighting`
+    pub y: Array1<f64>,
+    pub weighting: Weighting,
+    pub last: Option<(Array1<f64>, Array1<f64>)>, // the last iterates if (x, y) is an average
+    pub error: Vec<(usize, f64)>, // the errors of (x, y) at the evaluated iterations
+    pub time: Duration,
+    pub state: State,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weighting {
+    Last,      // (x, y) is the last iterate
+    Uniform,   // the uniform average of the iterates
+    Linear,    // the average weighted by the iteration number (CFR+)
+    StepSize,  // the average weighted by the step sizes (Mirror Prox)
+    Restarted, // the average since the last restart, or the restart point (PDHG)
+}
+
+// The internal state of a solver at the end
+#[derive(Clone, Debug)]
+pub enum State {
+    None,
+    Egt {
+        mu1: f64,
+        mu2: f64,
+        tau: f64,
+        restarts: usize,
+        bound: Vec<f64>, // the certified bounds mu1*D1 + mu2*D2 of the gap at every step
+    },
+    MirrorProx {
+        eta: f64,
+    },
+    Pdhg {
+        restarts: usize,
+        artificial: usize, // the restarts on the length of the period rather than the gap
+    },
+    DoubleOracle {
+        support: Vec<Support>,
+    },
+    Qre {
+        steps: usize, // the iterations of Newton's method along the homotopy
+    },
+}
+
+impl SolveResult {
+    pub fn new(x: Array1<f64>, y: Array1<f64>, error: Vec<(usize, f64)>, start: Instant) -> Self {
+        Self {
+            x,
+            y,
+            weighting: Weighting::Last,
+            last: None,
+            error,
+            time: start.elapsed(),
+            state: State::None,
+        }
+    }
+    pub fn average(mut self, weighting: Weighting, x: Array1<f64>, y: Array1<f64>) -> Self {
+        // Set the last iterates to (x, y) of `self` and replace them with the given average
+        self.last = Some((
+            std::mem::replace(&mut self.x, x),
+            std::mem::replace(&mut self.y, y),
+        ));
+        self.weighting = weighting;
+        self
+    }
+    pub fn state(mut self, state: State) -> Self {
+        self.state = state;
+        self
+    }
+    pub fn last_iterate(&self) -> (&Array1<f64>, &Array1<f64>) {
+        match &self.last {
+            Some((x, y)) => (x, y),
+            None => (&self.x, &self.y),
+        }
+    }
+    pub fn final_error(&self) -> f64 {
+        self.error.last().unwrap().1
+    }
+    pub fn iterations(&self) -> usize {
+        // The number of iterations run, including the initial one
+        self.error.last().unwrap().0 + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn average_keeps_the_last_iterate() {
+        let error = vec![(0, 1.0), (10, 0.5), (20, 0.25)];
+        let result = SolveResult::new(array![1.0, 0.0], array![0.0, 1.0], error, Instant::now());
+        assert_eq!(result.iterations(), 21);
+        assert_eq!(result.final_error(), 0.25);
+        assert_eq!(result.last_iterate(), (&result.x, &result.y));
+        let result = result.average(Weighting::Uniform, array![0.5, 0.5], array![0.25, 0.75]);
+        assert_eq!(result.weighting, Weighting::Uniform);
+        assert_eq!(
+            (&result.x, &result.y),
+            (&array![0.5, 0.5], &array![0.25, 0.75])
+        );
+        assert_eq!(
+            result.last_iterate(),
+            (&array![1.0, 0.0], &array![0.0, 1.0])
+        );
+        // error.json holds the pairs [iteration, error]
+        assert_eq!(
+            serde_json::to_string(&result.error).unwrap(),
+            "[[0,1.0],[10,0.5],[20,0.25]]"
+        );
+    }
+}
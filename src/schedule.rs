@@ -0,0 +1,237 @@
+// A hybrid schedule such as `cfr+:10%,egt[prox=centering]:90%`, whose phases run in order,
+// each warm-started from the strategies handed off by the previous one.
+
+use crate::result::SolveResult;
+use ndarray::Array1;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Budget {
+    Fraction(f64), // the fraction of the total number of iterations
+    Steps(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Handoff {
+    Average, // the strategies returned by the solver, i.e. the average for averaging solvers
+    Last,    // the last iterates
+}
+
+impl Handoff {
+    pub fn strategies(self, result: &SolveResult) -> (&Array1<f64>, &Array1<f64>) {
+        // Return the strategies of `result` that the next phase starts from
+        match self {
+            Handoff::Average => (&result.x, &result.y),
+            Handoff::Last => result.last_iterate(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Phase {
+    pub method: String,
+    pub options: Vec<(String, String)>, // overrides of the options, like `prox=centering`
+    pub budget: Budget,
+    pub handoff: Handoff,
+}
+
+fn split_top_level(spec: &str, sep: char) -> Vec<&str> {
+    // Split `spec` at `sep` outside of brackets
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (k, c) in spec.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ if c == sep && depth == 0 => {
+                parts.push(&spec[start..k]);
+                start = k + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&spec[start..]);
+    parts
+}
+
+fn parse_budget(spec: &str) -> Result<Budget, String> {
+    match spec.strip_suffix('%') {
+        Some(p) => match p.trim().parse::<f64>() {
+            Ok(p) if 0.0 < p && p <= 100.0 => Ok(Budget::Fraction(p / 100.0)),
+            _ => Err(format!(
+                "invalid budget `{}` (use p% with 0 < p <= 100)",
+                spec
+            )),
+        },
+        None => spec
+            .trim()
+            .parse::<usize>()
+            .map(Budget::Steps)
+            .map_err(|_| format!("invalid budget `{}` (use N% or N)", spec)),
+    }
+}
+
+pub fn parse_phase(spec: &str) -> Result<Phase, String> {
+    // Parse `method[key=value,...]:budget`
+    let (head, budget) = spec
+        .trim()
+        .rsplit_once(':')
+        .ok_or_else(|| format!("phase `{}` has no budget (e.g. `{}:50%`)", spec, spec))?;
+    let (method, options) = match head.split_once('[') {
+        None => (head, ""),
+        Some((method, rest)) => (
+            method,
+            rest.strip_suffix(']')
+                .ok_or_else(|| format!("unclosed `[` in phase `{}`", spec))?,
+        ),
+    };
+    let mut phase = Phase {
+        method: method.trim().to_string(),
+        options: vec![],
+        budget: parse_budget(budget)?,
+        handoff: Handoff::Average,
+    };
+    for option in split_top_level(options, ',') {
+        if option.trim().is_empty() {
+            continue;
+        }
+        let (key, value) = option
+            .split_once('=')
+            .ok_or_else(|| format!("option `{}` of phase `{}` is not key=value", option, spec))?;
+        let key = key.trim().replace('-', "_");
+        let value = value.trim().to_string();
+        if key == "handoff" {
+            phase.handoff = match value.as_str() {
+                "avg" | "average" => Handoff::Average,
+                "last" => Handoff::Last,
+                _ => return Err(format!("unknown hand-off `{}` (avg, last)", value)),
+            };
+        } else {
+            phase.options.push((key, value));
+        }
+    }
+    Ok(phase)
+}
+
+pub fn parse(spec: &str) -> Result<Vec<Phase>, String> {
+    let phases = split_top_level(spec, ',')
+        .into_iter()
+        .map(parse_phase)
+        .collect::<Result<Vec<_>, _>>()?;
+    let fraction: f64 = phases
+        .iter()
+        .map(|phase| match phase.budget {
+            Budget::Fraction(f) => f,
+            Budget::Steps(_) => 0.0,
+        })
+        .sum();
+    if fraction > 1.0 + 1e-9 {
+        return Err(format!(
+            "the budgets of `{}` add up to {:.1}% > 100%",
+            spec,
+            fraction * 100.0
+        ));
+    }
+    Ok(phases)
+}
+
+pub fn steps(phases: &[Phase], total: usize) -> Vec<usize> {
+    // Return the number of steps of each phase out of `total`, rounding the fractions
+    // cumulatively so that fractions adding up to 100% split `total` exactly
+    let mut fraction = 0.0;
+    let mut done = 0;
+    phases
+        .iter()
+        .map(|phase| match phase.budget {
+            Budget::Fraction(f) => {
+                fraction += f;
+                let end = ((fraction * total as f64).round() as usize).max(done);
+                let n = end - done;
+                done = end;
+                n
+            }
+            Budget::Steps(n) => n,
+        })
+        .collect()
+}
+
+pub fn is_schedule(spec: &str) -> bool {
+    spec.contains(':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::cfr_plus;
+    use crate::fictitious_play::xfp;
+    use crate::game::Game;
+
+    #[test]
+    fn parse_phases() {
+        let phases =
+            parse("cfr+:10%,egt[prox=centering, tau-growth=1.1,handoff=last]:90%").unwrap();
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[0].method, "cfr+");
+        assert!(phases[0].options.is_empty());
+        assert_eq!(phases[0].handoff, Handoff::Average);
+        assert_eq!(phases[1].method, "egt");
+        assert_eq!(
+            phases[1].options,
+            [
+                ("prox".to_string(), "centering".to_string()),
+                ("tau_growth".to_string(), "1.1".to_string())
+            ]
+        );
+        assert_eq!(phases[1].handoff, Handoff::Last);
+    }
+
+    #[test]
+    fn steps_add_up() {
+        for spec in [
+            "cfr+:10%,egt:90%",
+            "cfr:15%,egt:85%",
+            "cfr:33.3%,egt:33.3%,omd:33.4%",
+            "cfr:1%,cfr+:1%,egt:98%",
+        ] {
+            let phases = parse(spec).unwrap();
+            for total in [1, 7, 10, 99, 1000, 12345] {
+                let steps = steps(&phases, total);
+                assert_eq!(steps.iter().sum::<usize>(), total, "{} of {}", spec, total);
+            }
+        }
+        let phases = parse("cfr:100,egt:50%").unwrap();
+        assert_eq!(steps(&phases, 1000), [100, 500]);
+    }
+
+    #[test]
+    fn handoff_of_an_averaging_solver() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        let result = cfr_plus(&game, 50, None);
+        let (last_x, last_y) = result.last.as_ref().unwrap();
+        assert_ne!(&result.x, last_x);
+        assert_eq!(Handoff::Average.strategies(&result), (&result.x, &result.y));
+        assert_eq!(Handoff::Last.strategies(&result), (last_x, last_y));
+        // The last iterates of a solver without averaging are its strategies
+        let result = xfp(&game, 50);
+        assert!(result.last.is_none());
+        assert_eq!(Handoff::Last.strategies(&result), (&result.x, &result.y));
+    }
+
+    #[test]
+    fn malformed() {
+        for spec in [
+            "cfr:10%,egt",
+            "cfr:ten,egt:90%",
+            "cfr:-5,egt:90%",
+            "cfr:60%,egt:60%",
+            "egt[prox=centering:50%",
+            "egt[prox]:50%",
+            "egt[handoff=first]:50%",
+            "egt:-5%",
+            "egt:nan%",
+            "egt:0%",
+        ] {
+            assert!(parse(spec).is_err(), "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+// Cooperative stopping of the solvers, which check `requested` after every iteration and
+// return what they have so far. The deadline is per thread like the metrics sink, while
+// SIGINT and SIGTERM stop every thread; a second signal terminates the process at once.
+// Outside a `Cooperation` the signals terminate the process at once as by default.
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+struct Flags {
+    shutdown: Arc<AtomicBool>, // whether the next signal terminates the process
+    signal: Arc<AtomicUsize>,  // the first signal received while cooperating (0: none)
+}
+
+static FLAGS: OnceLock<Flags> = OnceLock::new();
+
+pub fn set_deadline(deadline: Option<Instant>) {
+    DEADLINE.with(|d| d.set(deadline));
+}
+
+pub fn handle_signals() {
+    // Exit with 128 + the signal number like the default action
+    let flags = FLAGS.get_or_init(|| Flags {
+        shutdown: Arc::new(AtomicBool::new(true)),
+        signal: Arc::new(AtomicUsize::new(0)),
+    });
+    for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        let (shutdown, flag) = (Arc::clone(&flags.shutdown), Arc::clone(&flags.signal));
+        signal_hook::flag::register_conditional_shutdown(signal, 128 + signal, shutdown).unwrap();
+        signal_hook::flag::register_usize(signal, flag, signal as usize).unwrap();
+        signal_hook::flag::register(signal, Arc::clone(&flags.shutdown)).unwrap();
+    }
+}
+
+// While alive, the first signal stops the solvers instead of terminating the process
+pub struct Cooperation(());
+
+pub fn cooperate() -> Cooperation {
+    if let Some(flags) = FLAGS.get() {
+        if flags.signal.load(Ordering::Relaxed) == 0 {
+            flags.shutdown.store(false, Ordering::Relaxed);
+        }
+    }
+    Cooperation(())
+}
+
+impl Drop for Cooperation {
+    fn drop(&mut self) {
+        if let Some(flags) = FLAGS.get() {
+            flags.shutdown.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn interrupted() -> bool {
+    exit_code().is_some()
+}
+
+pub fn exit_code() -> Option<i32> {
+    // Return 128 + the signal number if the solvers were stopped by a signal
+    let signal = FLAGS.get()?.signal.load(Ordering::Relaxed);
+    (signal > 0).then_some(128 + signal as i32)
+}
+
+pub fn timed_out() -> bool {
+    DEADLINE.with(|d| d.get().is_some_and(|deadline| Instant::now() >= deadline))
+}
+
+pub fn requested() -> bool {
+    interrupted() || timed_out()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::cfr_plus;
+    use crate::game::Game;
+
+    #[test]
+    fn passed_deadline_stops_the_solver() {
+        let game = Game::load(concat!(env!("CARGO_MANIFEST_DIR"), "/kuhn.json")).unwrap();
+        set_deadline(Some(Instant::now()));
+        assert!(timed_out() && requested() && !interrupted());
+        let result = cfr_plus(&game, 1000, None);
+        set_deadline(None);
+        assert!(!timed_out() && !requested());
+        // Only the initial iterate was evaluated, and it is returned as the partial result
+        assert_eq!(result.iterations(), 1);
+        assert!(result.final_error().is_finite());
+        assert_eq!(result.x.len(), *game.sp1.idx.last().unwrap());
+    }
+}